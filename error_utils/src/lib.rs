@@ -1,6 +1,7 @@
 use std::{
     error,
     io::{self, ErrorKind},
+    path::PathBuf,
 };
 use thiserror;
 use walkdir::Error as WalkDirError;
@@ -47,6 +48,15 @@ pub enum ProgramError<'a> {
         prg_name: &'a str,
         pathname: &'a str,
     },
+    #[error(
+        "{prg_name}: {pathname}: File system loop detected, back to {}",
+        ancestor.display()
+    )]
+    FilesystemLoop {
+        prg_name: &'a str,
+        pathname: &'a str,
+        ancestor: PathBuf,
+    },
     #[error("{prg_name}: {pathname}: {err}")]
     Other {
         prg_name: &'a str,
@@ -55,6 +65,28 @@ pub enum ProgramError<'a> {
     },
 }
 
+impl ProgramError<'_> {
+    /// the name of the variant, telling what kind of error it is
+    pub fn kind(&self) -> &'static str {
+        match self {
+            Self::FileNotFound { .. } => "FileNotFound",
+            Self::PermissionDenied { .. } => "PermissionDenied",
+            Self::FilesystemLoop { .. } => "FilesystemLoop",
+            Self::Other { .. } => "Other",
+        }
+    }
+
+    /// the path the error happened at
+    pub fn pathname(&self) -> &str {
+        match self {
+            Self::FileNotFound { pathname, .. }
+            | Self::PermissionDenied { pathname, .. }
+            | Self::FilesystemLoop { pathname, .. }
+            | Self::Other { pathname, .. } => pathname,
+        }
+    }
+}
+
 impl<'a> From<(io::Error, ProgramErrorParams<'a>)> for ProgramError<'a> {
     fn from((err, params): (io::Error, ProgramErrorParams<'a>)) -> Self {
         let (prg_name, pathname) = (params.prg_name.unwrap(), params.pathname.unwrap());
@@ -74,6 +106,13 @@ impl<'a> From<(io::Error, ProgramErrorParams<'a>)> for ProgramError<'a> {
 impl<'a> From<(Box<dyn error::Error>, ProgramErrorParams<'a>)> for ProgramError<'a> {
     fn from((err, params): (Box<dyn error::Error>, ProgramErrorParams<'a>)) -> Self {
         if let Some(e) = err.downcast_ref::<WalkDirError>() {
+            if let Some(ancestor) = e.loop_ancestor() {
+                return Self::FilesystemLoop {
+                    prg_name: params.prg_name.unwrap(),
+                    pathname: params.pathname.unwrap(),
+                    ancestor: ancestor.to_path_buf(),
+                };
+            }
             if let Some(io_err) = e.io_error() {
                 return (io::Error::new(io_err.kind(), io_err.to_string()), params).into();
             }
@@ -119,6 +158,40 @@ mod tests {
 
         let actual: ProgramError = (err, params).into();
         assert_eq!(actual.to_string(), "findr: blargh: Permission denied");
+        assert_eq!(actual.kind(), "PermissionDenied");
+        assert_eq!(actual.pathname(), "blargh");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn translate_filesystem_loop() -> anyhow::Result<()> {
+        use std::fs;
+        use std::os::unix::fs::symlink;
+        use walkdir::WalkDir;
+
+        let dir = tempfile::tempdir()?;
+        fs::create_dir(dir.path().join("a"))?;
+        symlink(dir.path(), dir.path().join("a/loop"))?;
+        let err = WalkDir::new(dir.path())
+            .follow_links(true)
+            .into_iter()
+            .find_map(Result::err)
+            .expect("finds the loop");
+        let params = ProgramErrorParams::new()
+            .pathname("a/loop")
+            .program("findr")
+            .build()?;
+
+        let actual: ProgramError = (Box::new(err) as Box<dyn error::Error>, params).into();
+        assert_eq!(
+            actual.to_string(),
+            format!(
+                "findr: a/loop: File system loop detected, back to {}",
+                dir.path().display()
+            )
+        );
+        assert_eq!(actual.kind(), "FilesystemLoop");
         Ok(())
     }
 
@@ -0,0 +1,63 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::fs;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use tempfile::TempDir;
+
+/// the number of entries of the generated tree, unless `FINDR_BENCH_ENTRIES` says otherwise
+const TREE_ENTRIES: usize = 1_000_000;
+
+/// a tree of three levels, each directory holding as many entries as the others
+fn generate_tree(entries: usize) -> TempDir {
+    let dir = tempfile::tempdir().expect("creates tree directory");
+    let fanout = (entries as f64).cbrt().ceil() as usize;
+    for i in 0..fanout {
+        for j in 0..fanout {
+            let leaf = dir.path().join(format!("dir{i}")).join(format!("dir{j}"));
+            fs::create_dir_all(&leaf).expect("creates directory");
+            for k in 0..fanout {
+                fs::write(leaf.join(format!("file{k}.txt")), "").expect("creates file");
+            }
+        }
+    }
+    dir
+}
+
+fn findr(root: &Path, args: &[&str]) {
+    let status = Command::new(env!("CARGO_BIN_EXE_findr"))
+        .arg(root)
+        .args(args)
+        .stdout(Stdio::null())
+        .status()
+        .expect("runs findr");
+    assert!(status.success(), "findr is successful");
+}
+
+fn bench_walking(c: &mut Criterion) {
+    let entries = std::env::var("FINDR_BENCH_ENTRIES")
+        .ok()
+        .and_then(|entries| entries.parse().ok())
+        .unwrap_or(TREE_ENTRIES);
+    let tree = generate_tree(entries);
+    let jobs = std::thread::available_parallelism()
+        .map_or(4, |jobs| jobs.get())
+        .to_string();
+
+    let mut group = c.benchmark_group("walk_tree");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(entries as u64));
+    group.bench_function("sequential", |b| b.iter(|| findr(tree.path(), &[])));
+    group.bench_function("parallel", |b| {
+        b.iter(|| findr(tree.path(), &["-j", &jobs]))
+    });
+    group.bench_function("parallel_sorted", |b| {
+        b.iter(|| findr(tree.path(), &["-j", &jobs, "--sort"]))
+    });
+    group.bench_function("parallel_name", |b| {
+        b.iter(|| findr(tree.path(), &["-j", &jobs, "-name", "file1*"]))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_walking);
+criterion_main!(benches);
@@ -1,24 +1,377 @@
-use clap::{builder::PossibleValue, Parser, ValueEnum};
-use regex::Regex;
+use crate::entry::Entry;
+use crate::expr::action::{Output, Print, Sink};
+use crate::expr::json::Field;
+use crate::expr::{self, pattern, Expr};
+use clap::{builder::PossibleValue, error::ErrorKind, ArgGroup, CommandFactory, Parser, ValueEnum};
+use globset::GlobMatcher;
+use std::cmp::Ordering;
+use std::env;
 use std::fs::FileType as LibFileType;
+use std::num::NonZeroUsize;
+
+const EXPRESSION_HELP: &str = "\
+Expression:
+  Entries can also be selected with a find-style expression following the paths, e.g.
+  findr . \\( -name '*.rs' -o -type d \\) -a ! -path '*/target/*'
+
+  ( EXPR )              Group expressions
+  ! EXPR, -not EXPR     Match entries not matching EXPR
+  EXPR -a EXPR          Match entries matching both, also written EXPR -and EXPR or EXPR EXPR
+  EXPR -o EXPR          Match entries matching either, also written EXPR -or EXPR
+  -true, -false         Match every entry, or none
+  -name GLOB            Match file names matching the shell glob, -iname ignores case
+  -path GLOB            Match paths matching the shell glob, -ipath ignores case
+  -regex PATTERN        Match whole paths matching the regular expression, -iregex ignores case
+  -regextype TYPE       Use the syntax rust (also posix-extended, egrep) or posix-basic
+                        for the following -regex patterns
+  -contains PATTERN     Match regular files with a line matching the regular expression,
+                        skipping binary files unless --binary is given; -icontains ignores case
+  -type TYPE            Match entries of the file type d, f, l, b (block device), c (character
+                        device), p (named pipe) or s (socket), or any of a list such as f,l
+  -xtype TYPE           Same as -type, for what a symbolic link points to, or for the link
+                        itself with -L. Broken links are of type l
+  -size [+-]N[bcwkMG]   Match sizes, rounded up to 512 byte blocks or the unit, of more than
+                        (+), less than (-) or exactly N
+  -mtime [+-]N          Match entries modified N days ago, ignoring fractions of days.
+                        -mmin counts minutes, -atime and -amin access, -ctime and -cmin changes
+  -newer FILE           Match entries modified more recently than FILE
+  -perm [-/]MODE        Match permissions exactly MODE, with all (-) or any (/) of its bits set
+  -user NAME, -uid N    Match entries owned by the user, -group and -gid by the group
+  -nouser, -nogroup     Match entries whose owner or group does not exist
+  -empty                Match empty regular files and directories
+  -prune                Do not descend into the directory, unless walking the contents of
+                        directories first; always true
+  -depth                Walk the contents of directories before the directories; always true
+  -xdev, -mount         Do not descend into directories on other file systems; always true
+  -fstype TYPE          Match entries on a file system of TYPE, such as ext4 or tmpfs (Linux)
+
+Actions:
+  Unless the expression has an action, the paths of the matching entries are printed.
+
+  -print                Print the path of the entry
+  -print0               Print the path of the entry followed by a NUL
+  -printf FORMAT        Print FORMAT, with directives such as %p (path), %f (name), %s (size),
+                        %m (octal mode), %u (user), %t (modification time), %T@ (modification
+                        time in seconds), %d (depth) and %y (type), taking printf widths
+  -fprint FILE          Same as -print, writing to FILE
+  -fprint0 FILE         Same as -print0, writing to FILE
+  -fprintf FILE FORMAT  Same as -printf, writing to FILE
+  -exec CMD ;           Run CMD, in which {} stands for the path, matching when it succeeds
+  -exec CMD {} +        Run CMD on as many paths at once as the system allows
+  -execdir CMD ;        Same as -exec, run in the directory of the entry on ./ and its name
+  -ok CMD ;             Same as -exec, after asking for confirmation
+  -delete               Delete the entry, walking the contents of directories first";
 
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(
+    version,
+    about,
+    long_about = None,
+    override_usage = "findr [-P|-L|-H] [OPTIONS] [PATHS]... [EXPRESSION]",
+    after_help = EXPRESSION_HELP,
+    group(ArgGroup::new("order").args(["sort", "newest", "largest"]).multiple(true))
+)]
 /// Partial implementation of find program in Rust
 pub struct FindrArgs {
+    /// Never follow symbolic links, the default
+    #[arg(short('P'), overrides_with_all(["follow", "follow_paths"]))]
+    pub physical: bool,
+    /// Follow all symbolic links, testing the entries they point to
+    #[arg(short('L'), overrides_with_all(["physical", "follow_paths"]))]
+    pub follow: bool,
+    /// Only follow the symbolic links given as paths to search
+    #[arg(short('H'), overrides_with_all(["physical", "follow"]))]
+    pub follow_paths: bool,
     /// Paths to search
     #[arg(default_value("."))]
     pub paths: Vec<String>,
-    /// Match file types ending with the specified name pattern
-    #[arg(short(None), long("name"),value_name("NAME"),num_args(0..))]
-    pub names: Vec<Regex>,
+    /// Match file names against the shell glob pattern
+    #[arg(short(None), long("name"),value_name("NAME"),num_args(0..),value_parser(parse_glob))]
+    pub names: Vec<GlobMatcher>,
     /// The file type to match
-    #[arg(short(None), long("type"),value_name("TYPE"),num_args(0..))]
+    #[arg(short(None), long("type"),value_name("TYPE"),num_args(0..),value_delimiter(','))]
     pub file_types: Vec<FileType>,
     #[arg(short(None), long("maxdepth"))]
     pub max_depth: Option<usize>,
     #[arg(short(None), long("mindepth"))]
     pub min_depth: Option<usize>,
+    /// Walk the directories with N threads, in no particular order unless sorted
+    #[arg(short('j'), long("jobs"), value_name("N"), default_value("1"))]
+    pub jobs: NonZeroUsize,
+    /// Walk the entries of each directory in the order of their names (path, the default), or
+    /// test all the entries of each path in the order of their names, sizes or modification
+    /// times
+    #[arg(
+        short(None),
+        long("sort"),
+        value_name("KEY"),
+        num_args(0..=1),
+        default_missing_value("path")
+    )]
+    pub sort: Option<SortKey>,
+    /// Reverse the order of --sort, --newest or --largest
+    #[arg(short(None), long("reverse"), requires("order"))]
+    pub reverse: bool,
+    /// Stop walking once N entries matched
+    #[arg(short(None), long("limit"), value_name("N"))]
+    pub limit: Option<usize>,
+    /// Print the N most recently modified entries matching, the newest first, instead of all
+    /// the entries matching
+    #[arg(
+        short(None),
+        long("newest"),
+        value_name("N"),
+        conflicts_with_all(["largest", "duplicates"])
+    )]
+    pub newest: Option<usize>,
+    /// Print the N largest entries matching, the largest first, instead of all the entries
+    /// matching
+    #[arg(
+        short(None),
+        long("largest"),
+        value_name("N"),
+        conflicts_with("duplicates")
+    )]
+    pub largest: Option<usize>,
+    /// Skip the entries ignored by .gitignore, .ignore, .git/info/exclude and the global git
+    /// excludes, along with .git directories
+    #[arg(short(None), long("respect-ignore"))]
+    pub respect_ignore: bool,
+    /// Also search the contents of binary files with -contains and -icontains
+    #[arg(short(None), long("binary"))]
+    pub binary: bool,
+    /// Print the entries matching as text, the paths on their own lines, or as ndjson, a JSON
+    /// object per line, with errors reported as JSON objects as well
+    #[arg(short(None), long("format"), value_name("FORMAT"))]
+    pub format: Option<OutputFormat>,
+    /// The fields of the JSON objects printed with --format ndjson, all of them by default
+    #[arg(
+        short(None),
+        long("fields"),
+        value_name("FIELDS"),
+        value_delimiter(','),
+        requires("format")
+    )]
+    pub fields: Vec<Field>,
+    /// Print the total size of the regular files matching in each directory, after its
+    /// contents, as du does
+    #[arg(
+        short(None),
+        long("du"),
+        conflicts_with_all(["duplicates", "newest", "largest"])
+    )]
+    pub du: bool,
+    /// Print the groups of regular files with identical contents, separated by blank lines,
+    /// instead of the entries matching
+    #[arg(short(None), long("duplicates"))]
+    pub duplicates: bool,
+    /// The size of the smallest files compared with --duplicates, in bytes unless followed by
+    /// k, M or G
+    #[arg(
+        short(None),
+        long("min-size"),
+        value_name("SIZE"),
+        default_value("1"),
+        value_parser(parse_size),
+        requires("duplicates")
+    )]
+    pub min_size: u64,
+    /// Print each group of duplicates as a line of JSON, as --format ndjson does
+    #[arg(short(None), long("json"), requires("duplicates"))]
+    pub json: bool,
+    /// The find-style expression following the paths
+    #[arg(skip)]
+    pub expression: Option<Expr>,
+}
+
+impl FindrArgs {
+    /// Parse the command line options along with the find-style expression, exiting with a
+    /// usage error pointing at the offending token when the expression is invalid
+    pub fn parse_with_expression() -> Self {
+        let (clap_args, tokens) = expr::split_args(env::args_os());
+        let mut args = Self::parse_from(clap_args);
+        match expr::parse(&tokens) {
+            Ok(expression) => args.expression = expression,
+            Err(e) => Self::command()
+                .error(ErrorKind::InvalidValue, e.render(&tokens))
+                .exit(),
+        }
+        // -delete needs the contents of each directory gone before it, and --du added up, which
+        // only the order of the walk guarantees, as it does the contents coming first for -depth
+        let expression = args.expression.as_ref();
+        let contents_first = if args.du {
+            Some("--du")
+        } else if expression.is_some_and(Expr::deletes) {
+            Some("-delete")
+        } else if expression.is_some_and(Expr::contents_first) {
+            Some("-depth")
+        } else {
+            None
+        };
+        let key = args.sort.filter(|&key| key != SortKey::Path);
+        if let (Some(action), Some(key)) = (contents_first, key) {
+            let key = key.to_possible_value().expect("sort keys have names");
+            let msg = format!("{action} cannot be used with '--sort {}'", key.get_name());
+            Self::command()
+                .error(ErrorKind::ArgumentConflict, msg)
+                .exit();
+        }
+        // the groups of duplicates, or the entries ranking highest, are printed instead of the
+        // entries matching
+        let acts = expression.is_some_and(Expr::has_action);
+        let replacing = [
+            ("--duplicates", args.duplicates),
+            ("--newest", args.newest.is_some()),
+            ("--largest", args.largest.is_some()),
+        ];
+        if let Some((option, _)) = replacing.iter().find(|(_, given)| acts && *given) {
+            let msg = format!("actions such as -print or -exec cannot be used with '{option}'");
+            Self::command()
+                .error(ErrorKind::ArgumentConflict, msg)
+                .exit();
+        }
+        args
+    }
+
+    /// whether the output is JSON
+    pub fn is_json(&self) -> bool {
+        self.format == Some(OutputFormat::Ndjson)
+    }
+
+    /// what prints the entries when the expression has no action of its own
+    pub fn default_print(&self) -> Print {
+        match self.format {
+            Some(OutputFormat::Ndjson) => {
+                let fields = match self.fields.is_empty() {
+                    true => Field::ALL.to_vec(),
+                    false => self.fields.clone(),
+                };
+                Print::new(Output::Json(fields), Sink::Stdout)
+            }
+            Some(OutputFormat::Text) | None => Print::default(),
+        }
+    }
+
+    /// The least and the greatest depth to walk to. `--du` walks every depth, so that the
+    /// totals hold all the contents of the directories, and only leaves the directories
+    /// outside the limits unprinted
+    pub fn walk_depths(&self) -> (Option<usize>, Option<usize>) {
+        match self.du {
+            true => (None, None),
+            false => (self.min_depth, self.max_depth),
+        }
+    }
+
+    /// which symbolic links to follow, the last of -P, -L and -H deciding
+    pub fn symlinks(&self) -> Symlinks {
+        if self.follow {
+            Symlinks::All
+        } else if self.follow_paths {
+            Symlinks::Paths
+        } else {
+            Symlinks::None
+        }
+    }
+
+    /// The expression each entry must match: the `--name` and `--type` options each match any
+    /// of their values, and both must match along with the find-style expression
+    pub fn filter(&self) -> Option<Expr> {
+        Expr::all(
+            [
+                Expr::any(self.names.iter().cloned().map(Expr::Name)),
+                Expr::any(self.file_types.iter().copied().map(Expr::Type)),
+                self.expression.clone().map(|mut expr| {
+                    if self.binary {
+                        expr.include_binary();
+                    }
+                    expr
+                }),
+            ]
+            .into_iter()
+            .flatten(),
+        )
+    }
+}
+
+fn parse_glob(pattern: &str) -> Result<GlobMatcher, globset::Error> {
+    pattern::glob(pattern, false)
+}
+
+/// a size in bytes, or in kibibytes, mebibytes or gibibytes when followed by k, M or G
+fn parse_size(size: &str) -> Result<u64, String> {
+    let (digits, unit) = match size.char_indices().last() {
+        Some((i, 'k')) => (&size[..i], 1 << 10),
+        Some((i, 'M')) => (&size[..i], 1 << 20),
+        Some((i, 'G')) => (&size[..i], 1 << 30),
+        _ => (size, 1),
+    };
+    digits
+        .parse::<u64>()
+        .ok()
+        .and_then(|n| n.checked_mul(unit))
+        .ok_or_else(|| format!("invalid size '{}'", size))
+}
+
+/// How `--format` prints the entries
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Ndjson,
+}
+
+/// What `--sort` orders the entries by
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// the file names, the whole paths breaking ties
+    Name,
+    /// the names of the entries in each directory, which come right after the directory
+    Path,
+    /// the sizes, smallest first
+    Size,
+    /// the modification times, oldest first
+    Mtime,
+}
+
+impl SortKey {
+    /// Compare the entries by the key, then by their paths. The entries whose metadata cannot
+    /// be read come first
+    pub fn compare(self, a: &Entry, b: &Entry) -> Ordering {
+        let by_key = match self {
+            Self::Name => a.file_name().cmp(b.file_name()),
+            Self::Path => Ordering::Equal,
+            Self::Size => {
+                let len = |entry: &Entry| entry.metadata().map(|m| m.len());
+                len(a).cmp(&len(b))
+            }
+            Self::Mtime => {
+                let modified = |entry: &Entry| entry.metadata().and_then(|m| m.modified().ok());
+                modified(a).cmp(&modified(b))
+            }
+        };
+        by_key.then_with(|| a.path().cmp(b.path()))
+    }
+}
+
+/// The symbolic links followed while walking
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Symlinks {
+    /// `-P`
+    None,
+    /// `-L`
+    All,
+    /// `-H`
+    Paths,
+}
+
+impl Symlinks {
+    /// whether a symbolic link found at the depth is followed
+    pub fn follows(self, depth: usize) -> bool {
+        match self {
+            Self::None => false,
+            Self::All => true,
+            Self::Paths => depth == 0,
+        }
+    }
 }
 
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
@@ -29,11 +382,35 @@ pub enum FileType {
     File,
     /// Symbolic link
     Link,
+    /// Block device
+    #[cfg(unix)]
+    Block,
+    /// Character device
+    #[cfg(unix)]
+    Char,
+    /// Named pipe
+    #[cfg(unix)]
+    Fifo,
+    /// Unix domain socket
+    #[cfg(unix)]
+    Socket,
 }
 
 impl ValueEnum for FileType {
     fn value_variants<'a>() -> &'a [Self] {
-        &[FileType::File, FileType::Dir, FileType::Link]
+        &[
+            FileType::Dir,
+            FileType::File,
+            FileType::Link,
+            #[cfg(unix)]
+            FileType::Block,
+            #[cfg(unix)]
+            FileType::Char,
+            #[cfg(unix)]
+            FileType::Fifo,
+            #[cfg(unix)]
+            FileType::Socket,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
@@ -41,6 +418,14 @@ impl ValueEnum for FileType {
             FileType::Dir => PossibleValue::new("d"),
             FileType::File => PossibleValue::new("f"),
             FileType::Link => PossibleValue::new("l"),
+            #[cfg(unix)]
+            FileType::Block => PossibleValue::new("b"),
+            #[cfg(unix)]
+            FileType::Char => PossibleValue::new("c"),
+            #[cfg(unix)]
+            FileType::Fifo => PossibleValue::new("p"),
+            #[cfg(unix)]
+            FileType::Socket => PossibleValue::new("s"),
         };
         Some(val)
     }
@@ -48,10 +433,38 @@ impl ValueEnum for FileType {
 
 impl FileType {
     pub fn is_type(&self, file_type: &LibFileType) -> bool {
+        #[cfg(unix)]
+        use std::os::unix::fs::FileTypeExt;
+
         match self {
             Self::Dir => file_type.is_dir(),
             Self::File => file_type.is_file(),
             Self::Link => file_type.is_symlink(),
+            #[cfg(unix)]
+            Self::Block => file_type.is_block_device(),
+            #[cfg(unix)]
+            Self::Char => file_type.is_char_device(),
+            #[cfg(unix)]
+            Self::Fifo => file_type.is_fifo(),
+            #[cfg(unix)]
+            Self::Socket => file_type.is_socket(),
         }
     }
+
+    /// the letters of the file types, as `-type` takes them
+    pub fn names() -> String {
+        Self::value_variants()
+            .iter()
+            .filter_map(|file_type| file_type.to_possible_value())
+            .map(|value| value.get_name().to_string())
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    /// the file types of a comma-separated list such as `f,l`, as GNU find takes them
+    pub fn parse_list(list: &str) -> Option<Vec<Self>> {
+        list.split(',')
+            .map(|name| Self::from_str(name, false).ok())
+            .collect()
+    }
 }
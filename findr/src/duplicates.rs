@@ -0,0 +1,220 @@
+use crate::entry::Entry;
+use crate::expr::json;
+use crate::{output, translate_error};
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::hash::Hash;
+use std::io::{self, Read};
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// the bytes at the start of the files hashed first, which tell most files of the same size
+/// apart without reading them whole
+const BLOCK_SIZE: u64 = 4096;
+
+/// A regular file gathered to compare its contents with those of the others
+#[derive(Debug)]
+struct Candidate {
+    path: PathBuf,
+    size: u64,
+    /// the device and inode numbers, which hard links to the file share
+    inode: Option<(u64, u64)>,
+}
+
+/// `--duplicates`: the regular files matching the expression, gathered by every thread walking
+/// the paths, to be grouped by their contents once the walk is done
+#[derive(Debug, Clone)]
+pub struct Files {
+    min_size: u64,
+    candidates: Arc<Mutex<Vec<Candidate>>>,
+}
+
+/// Files with identical contents, by path
+#[derive(Debug, PartialEq, Eq)]
+pub struct Group {
+    pub size: u64,
+    pub paths: Vec<PathBuf>,
+}
+
+impl Files {
+    pub fn new(min_size: u64) -> Self {
+        Self {
+            min_size,
+            candidates: Arc::default(),
+        }
+    }
+
+    /// gather the entry if it is a regular file of at least the minimum size, returning whether
+    /// it was
+    pub fn gather(&self, entry: &Entry) -> bool {
+        if !entry.file_type().is_file() {
+            return false;
+        }
+        let Some(metadata) = entry.metadata().filter(|m| m.len() >= self.min_size) else {
+            return false;
+        };
+        #[cfg(unix)]
+        let inode = Some((metadata.dev(), metadata.ino()));
+        #[cfg(not(unix))]
+        let inode = None;
+        self.candidates
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+            .push(Candidate {
+                path: entry.path().to_path_buf(),
+                size: metadata.len(),
+                inode,
+            });
+        true
+    }
+
+    /// Group the files gathered with identical contents, in the order of their paths. Files are
+    /// compared by size first, then by a hash of their first block, and only then by a hash of
+    /// their whole contents, so that most files are never read whole. A file found again, by a
+    /// hard link or by overlapping paths to search, is left out rather than taken as a
+    /// duplicate of itself
+    pub fn groups(&self) -> Vec<Group> {
+        let mut candidates = std::mem::take(
+            &mut *self
+                .candidates
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner()),
+        );
+        candidates.sort_unstable_by(|a, b| a.path.cmp(&b.path));
+        let mut inodes = HashSet::new();
+        candidates.retain(|c| c.inode.is_none_or(|inode| inodes.insert(inode)));
+
+        let mut groups: Vec<Group> = bucket(candidates, |c| Some(c.size))
+            .into_iter()
+            .flat_map(|same_size| bucket(same_size, |c| hash(c, Some(BLOCK_SIZE))))
+            .flat_map(|same_start| match same_start[0].size <= BLOCK_SIZE {
+                true => vec![same_start],
+                false => bucket(same_start, |c| hash(c, None)),
+            })
+            .map(|same| Group {
+                size: same[0].size,
+                paths: same.into_iter().map(|c| c.path).collect(),
+            })
+            .collect();
+        groups.sort_unstable_by(|a, b| a.paths.cmp(&b.paths));
+        groups
+    }
+}
+
+/// the candidates sharing a key with others, in buckets keeping their order. Those without a
+/// key are left out
+fn bucket<K: Hash + Eq>(
+    candidates: Vec<Candidate>,
+    key: impl Fn(&Candidate) -> Option<K>,
+) -> Vec<Vec<Candidate>> {
+    let mut buckets: HashMap<K, Vec<Candidate>> = HashMap::new();
+    for candidate in candidates {
+        if let Some(key) = key(&candidate) {
+            buckets.entry(key).or_default().push(candidate);
+        }
+    }
+    buckets
+        .into_values()
+        .filter(|bucket| bucket.len() > 1)
+        .collect()
+}
+
+/// the hash of the contents of the file, or of up to `limit` bytes of them, reporting the file
+/// when it cannot be read
+fn hash(candidate: &Candidate, limit: Option<u64>) -> Option<blake3::Hash> {
+    let res = File::open(&candidate.path).and_then(|file| {
+        let mut hasher = blake3::Hasher::new();
+        match limit {
+            Some(limit) => io::copy(&mut file.take(limit), &mut hasher),
+            None => io::copy(&mut &file, &mut hasher),
+        }?;
+        Ok(hasher.finalize())
+    });
+    match res {
+        Ok(hash) => Some(hash),
+        Err(e) => {
+            let pathname = candidate.path.to_string_lossy();
+            output::report(translate_error(Box::new(e), &pathname));
+            None
+        }
+    }
+}
+
+/// Print the paths of each group on their own lines, with a blank line between groups, or each
+/// group as a line of JSON giving the size of the files and their paths
+pub fn print(groups: &[Group], json: bool) {
+    let res = groups.iter().enumerate().try_for_each(|(i, group)| {
+        if json {
+            let paths: Vec<_> = group.paths.iter().map(|p| json::path(p)).collect();
+            let line = serde_json::json!({ "size": group.size, "paths": paths });
+            return output::print(format!("{}\n", line).as_bytes());
+        }
+        if i > 0 {
+            output::print(b"\n")?;
+        }
+        group.paths.iter().try_for_each(|path| {
+            let mut line = path.as_os_str().as_encoded_bytes().to_vec();
+            line.push(b'\n');
+            output::print(&line)
+        })
+    });
+    if let Err(e) = res.and_then(|()| output::flush()) {
+        output::report(translate_error(Box::new(e), "standard output"));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use std::path::Path;
+
+    fn entry(path: &Path) -> Entry {
+        let entry = walkdir::WalkDir::new(path).into_iter().next();
+        Entry::from(entry.expect("file exists").expect("file is readable"))
+    }
+
+    fn gather(files: &Files, path: &Path) {
+        assert!(files.gather(&entry(path)), "gathers {}", path.display());
+    }
+
+    #[test]
+    fn group_identical_contents() -> io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let path = |name: &str| dir.path().join(name);
+        let block = vec![b'a'; BLOCK_SIZE as usize];
+        fs::write(path("a1"), "a")?;
+        fs::write(path("a2"), "a")?;
+        fs::write(path("b"), "b")?;
+        fs::write(path("long1"), [&block[..], b"x"].concat())?;
+        fs::write(path("long2"), [&block[..], b"x"].concat())?;
+        fs::write(path("long3"), [&block[..], b"y"].concat())?;
+        fs::write(path("empty"), "")?;
+        #[cfg(unix)]
+        fs::hard_link(path("b"), path("b_link"))?;
+
+        let files = Files::new(1);
+        for name in ["long3", "b", "a2", "long2", "a1", "long1"] {
+            gather(&files, &path(name));
+        }
+        #[cfg(unix)]
+        gather(&files, &path("b_link"));
+        assert!(!files.gather(&entry(&path("empty"))));
+        assert_eq!(
+            files.groups(),
+            [
+                Group {
+                    size: 1,
+                    paths: vec![path("a1"), path("a2")]
+                },
+                Group {
+                    size: BLOCK_SIZE + 1,
+                    paths: vec![path("long1"), path("long2")]
+                },
+            ]
+        );
+        Ok(())
+    }
+}
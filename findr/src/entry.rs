@@ -0,0 +1,152 @@
+use crate::{output, translate_error};
+use std::cell::{Cell, OnceCell};
+use std::ffi::OsStr;
+use std::fs::{self, FileType, Metadata};
+use std::path::{Path, PathBuf};
+
+/// An entry found while walking the paths, by either walker. Its metadata is only read the
+/// first time an expression needs it, then kept for the other tests on the same entry
+#[derive(Debug, Clone)]
+pub struct Entry {
+    path: PathBuf,
+    file_type: FileType,
+    depth: usize,
+    /// whether the entry is seen through the symbolic link it was found as
+    followed: bool,
+    metadata: OnceCell<Option<Metadata>>,
+    /// whether `-prune` asked to skip the contents of the entry
+    pruned: Cell<bool>,
+}
+
+impl From<walkdir::DirEntry> for Entry {
+    fn from(dir_entry: walkdir::DirEntry) -> Self {
+        Self::new(
+            dir_entry.file_type(),
+            dir_entry.depth(),
+            dir_entry.into_path(),
+        )
+    }
+}
+
+impl From<ignore::DirEntry> for Entry {
+    fn from(dir_entry: ignore::DirEntry) -> Self {
+        let file_type = dir_entry
+            .file_type()
+            .expect("only standard input has no file type");
+        Self::new(file_type, dir_entry.depth(), dir_entry.into_path())
+    }
+}
+
+impl Entry {
+    fn new(file_type: FileType, depth: usize, path: PathBuf) -> Self {
+        Self {
+            path,
+            file_type,
+            depth,
+            followed: false,
+            metadata: OnceCell::new(),
+            pruned: Cell::new(false),
+        }
+    }
+
+    /// the symbolic link at the path, tested as itself rather than what it points to, as find
+    /// does when the link is broken
+    pub fn link(path: &Path, depth: usize) -> Option<Self> {
+        fs::symlink_metadata(path)
+            .ok()
+            .filter(|metadata| metadata.file_type().is_symlink())
+            .map(|metadata| Self::new(metadata.file_type(), depth, path.to_path_buf()))
+    }
+
+    /// test the entry the symbolic link points to, if the entry is one and the walker followed
+    /// it
+    pub fn follow_links(mut self, follow: bool) -> Self {
+        self.followed = follow && !self.file_type.is_symlink();
+        self
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// the last component of the path, or the whole path when it has none, such as `.`
+    pub fn file_name(&self) -> &OsStr {
+        self.path.file_name().unwrap_or(self.path.as_os_str())
+    }
+
+    /// how many directories down from the starting path the entry is
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// the starting path the entry was found under
+    pub fn root(&self) -> &Path {
+        self.path()
+            .ancestors()
+            .nth(self.depth())
+            .unwrap_or_else(|| self.path())
+    }
+
+    pub fn file_type(&self) -> FileType {
+        self.file_type
+    }
+
+    /// the type of the entry on the other side of a symbolic link: what the link points to when
+    /// it was not followed, the link itself when it was. A broken link is a link either way
+    pub fn other_file_type(&self) -> FileType {
+        let other = if self.followed {
+            fs::symlink_metadata(self.path())
+        } else if self.file_type.is_symlink() {
+            fs::metadata(self.path())
+        } else {
+            return self.file_type;
+        };
+        other.map_or(self.file_type, |metadata| metadata.file_type())
+    }
+
+    /// the metadata of the entry, or `None` once the reason it could not be read is reported
+    pub fn metadata(&self) -> Option<&Metadata> {
+        self.metadata
+            .get_or_init(|| {
+                let metadata = match self.followed {
+                    true => fs::metadata(self.path()),
+                    false => fs::symlink_metadata(self.path()),
+                };
+                match metadata {
+                    Ok(metadata) => Some(metadata),
+                    Err(e) => {
+                        let pathname = self.path().to_string_lossy();
+                        output::report(translate_error(Box::new(e), &pathname));
+                        None
+                    }
+                }
+            })
+            .as_ref()
+    }
+
+    /// skip the contents of the entry, if it is a directory
+    pub fn prune(&self) {
+        self.pruned.set(true);
+    }
+
+    /// whether the walk skips the contents of the entry
+    pub fn is_pruned(&self) -> bool {
+        self.pruned.get() && self.file_type.is_dir()
+    }
+
+    /// whether the entry is an empty regular file or directory
+    pub fn is_empty(&self) -> bool {
+        let file_type = self.file_type();
+        if file_type.is_dir() {
+            return match fs::read_dir(self.path()) {
+                Ok(mut entries) => entries.next().is_none(),
+                Err(e) => {
+                    let pathname = self.path().to_string_lossy();
+                    output::report(translate_error(Box::new(e), &pathname));
+                    false
+                }
+            };
+        }
+        file_type.is_file() && self.metadata().is_some_and(|metadata| metadata.len() == 0)
+    }
+}
@@ -0,0 +1,291 @@
+use crate::cli::FileType;
+use crate::duplicates::Files;
+use crate::entry::Entry;
+use crate::results::Top;
+use crate::usage::Usage;
+use action::{Exec, Print};
+use globset::GlobMatcher;
+use metadata::{Size, Time};
+use regex::Regex;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::time::SystemTime;
+
+pub mod action;
+pub mod content;
+mod format;
+pub mod json;
+pub mod metadata;
+#[cfg(target_os = "linux")]
+pub mod mounts;
+mod parser;
+pub mod pattern;
+
+pub use parser::{parse, split_args};
+
+/// A find-style expression, evaluated against each entry found while walking the paths
+#[derive(Debug, Clone)]
+pub enum Expr {
+    /// `-true`, also what `-regextype` evaluates to
+    True,
+    /// `-false`
+    False,
+    /// `-name GLOB` or `-iname GLOB`: the file name matches the glob
+    Name(GlobMatcher),
+    /// `-path GLOB` or `-ipath GLOB`: the whole path matches the glob
+    Path(GlobMatcher),
+    /// `-regex PATTERN` or `-iregex PATTERN`: the whole path matches the regular expression
+    Regex(Regex),
+    /// `-contains PATTERN` or `-icontains PATTERN`: a line of the regular file matches
+    Contains(content::Contains),
+    /// `-type TYPE`: the entry is of the given file type
+    Type(FileType),
+    /// `-xtype TYPE`: the entry is of the given file type on the other side of a symbolic link
+    XType(FileType),
+    /// `-size [+-]N[bcwkMG]`
+    Size(Size),
+    /// `-atime`, `-amin`, `-mtime`, `-mmin`, `-ctime` or `-cmin`
+    Time(Time),
+    /// `-newer FILE`: the entry was modified more recently than the file
+    Newer(SystemTime),
+    /// `-perm MODE`, `-perm -MODE` or `-perm /MODE`
+    #[cfg(unix)]
+    Perm(metadata::Perm),
+    /// `-uid [+-]N`, or `-user NAME` with the user id of the name
+    #[cfg(unix)]
+    Uid(metadata::Numeric),
+    /// `-gid [+-]N`, or `-group NAME` with the group id of the name
+    #[cfg(unix)]
+    Gid(metadata::Numeric),
+    /// `-nouser`: no user has the user id of the entry
+    #[cfg(unix)]
+    NoUser,
+    /// `-nogroup`: no group has the group id of the entry
+    #[cfg(unix)]
+    NoGroup,
+    /// `-fstype TYPE`: the entry is on a file system of the type
+    #[cfg(target_os = "linux")]
+    FsType(mounts::FsType),
+    /// `-xdev` or `-mount`: do not walk into directories on other file systems than the path
+    /// they are found in; always true
+    XDev,
+    /// `-empty`: the entry is an empty regular file or directory
+    Empty,
+    /// `-prune`: skip the contents of the entry if it is a directory, unless walking the
+    /// contents of directories first
+    Prune,
+    /// `-depth`: walk the contents of directories before the directories themselves; always
+    /// true
+    Depth,
+    /// `-print`, `-print0`, `-printf` or their `-fprint` variants writing to a file
+    Print(Print),
+    /// `--duplicates`: gathers the regular files, to be grouped by their contents once the walk
+    /// is done
+    Gather(Files),
+    /// `--newest N` or `--largest N`: keeps the entry while it ranks among the N highest
+    Keep(Top),
+    /// `--du`: adds up the sizes of the regular files in each directory, printing the total
+    /// once its contents are done
+    Usage(Usage),
+    /// `-delete`: delete the entry
+    Delete,
+    /// `-exec`, `-execdir` or `-ok`: run a command on the entry
+    Exec(Exec),
+    /// `! EXPR` or `-not EXPR`
+    Not(Box<Expr>),
+    /// `EXPR -a EXPR`, `EXPR -and EXPR` or two expressions next to each other
+    And(Box<Expr>, Box<Expr>),
+    /// `EXPR -o EXPR` or `EXPR -or EXPR`
+    Or(Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    /// The expression to evaluate on each entry. Unless the expression already has an action,
+    /// each entry matching it is printed as `print` does
+    pub fn with_default_action(expr: Option<Expr>, print: Print) -> Expr {
+        match expr {
+            Some(expr) if expr.has_action() => expr,
+            expr => {
+                Self::all(expr.into_iter().chain([Self::Print(print)])).expect("has an expression")
+            }
+        }
+    }
+
+    /// whether the expression deletes entries, which have to be walked contents first
+    pub fn deletes(&self) -> bool {
+        self.contains(&|expr| matches!(expr, Self::Delete))
+    }
+
+    /// whether the contents of directories are walked before the directories themselves, as
+    /// `-depth`, `-delete` and `--du` need
+    pub fn contents_first(&self) -> bool {
+        self.contains(&|expr| matches!(expr, Self::Depth | Self::Delete | Self::Usage(_)))
+    }
+
+    /// whether the expression runs an action of its own, such as `-print` or `-exec`
+    pub fn has_action(&self) -> bool {
+        self.contains(&Self::is_action)
+    }
+
+    /// whether the expression may skip the contents of directories
+    pub fn may_prune(&self) -> bool {
+        self.contains(&|expr| matches!(expr, Self::Prune))
+    }
+
+    /// Whether evaluating the expression on the entry reaches `-prune`, evaluating its tests
+    /// but taking its actions as true without running them. This tells which directories to
+    /// leave the contents of before the entries are evaluated in another order than the walk
+    pub fn prunes(&self, entry: &Entry) -> bool {
+        let mut prunes = false;
+        self.eval_tests(entry, &mut prunes);
+        prunes
+    }
+
+    fn eval_tests(&self, entry: &Entry, prunes: &mut bool) -> bool {
+        match self {
+            Self::Prune => {
+                *prunes = true;
+                true
+            }
+            Self::Not(expr) => !expr.eval_tests(entry, prunes),
+            Self::And(left, right) => {
+                left.eval_tests(entry, prunes) && right.eval_tests(entry, prunes)
+            }
+            Self::Or(left, right) => {
+                left.eval_tests(entry, prunes) || right.eval_tests(entry, prunes)
+            }
+            expr if expr.is_action() => true,
+            test => test.clone().eval(entry),
+        }
+    }
+
+    /// whether the walk stays on the file system of the paths it starts from
+    pub fn stays_on_file_system(&self) -> bool {
+        self.contains(&|expr| matches!(expr, Self::XDev))
+    }
+
+    fn is_action(&self) -> bool {
+        matches!(
+            self,
+            Self::Print(_)
+                | Self::Gather(_)
+                | Self::Keep(_)
+                | Self::Usage(_)
+                | Self::Delete
+                | Self::Exec(_)
+        )
+    }
+
+    /// whether the expression or any expression within it is one for which `f` is true
+    fn contains(&self, f: &impl Fn(&Expr) -> bool) -> bool {
+        f(self)
+            || match self {
+                Self::Not(expr) => expr.contains(f),
+                Self::And(left, right) | Self::Or(left, right) => {
+                    left.contains(f) || right.contains(f)
+                }
+                _ => false,
+            }
+    }
+
+    /// Evaluate the expression on the entry, running the actions it reaches, and return whether
+    /// the entry matches. The right side of `-and` and `-or` is only evaluated when the left
+    /// side does not already decide the result
+    pub fn eval(&mut self, entry: &Entry) -> bool {
+        match self {
+            Self::True => true,
+            Self::False => false,
+            Self::Name(glob) => glob.is_match(entry.file_name()),
+            Self::Path(glob) => glob.is_match(entry.path()),
+            Self::Regex(regex) => regex.is_match(&entry.path().to_string_lossy()),
+            Self::Contains(contains) => contains.matches(entry),
+            Self::Type(file_type) => file_type.is_type(&entry.file_type()),
+            Self::XType(file_type) => file_type.is_type(&entry.other_file_type()),
+            Self::Size(size) => entry.metadata().is_some_and(|m| size.matches(m.len())),
+            Self::Time(time) => entry.metadata().is_some_and(|m| time.matches(m)),
+            Self::Newer(reference) => entry
+                .metadata()
+                .and_then(|m| m.modified().ok())
+                .is_some_and(|modified| modified > *reference),
+            #[cfg(unix)]
+            Self::Perm(perm) => entry.metadata().is_some_and(|m| perm.matches(m.mode())),
+            #[cfg(unix)]
+            Self::Uid(uid) => entry
+                .metadata()
+                .is_some_and(|m| uid.matches(m.uid().into())),
+            #[cfg(unix)]
+            Self::Gid(gid) => entry
+                .metadata()
+                .is_some_and(|m| gid.matches(m.gid().into())),
+            #[cfg(unix)]
+            Self::NoUser => entry
+                .metadata()
+                .is_some_and(|m| uzers::get_user_by_uid(m.uid()).is_none()),
+            #[cfg(unix)]
+            Self::NoGroup => entry
+                .metadata()
+                .is_some_and(|m| uzers::get_group_by_gid(m.gid()).is_none()),
+            #[cfg(target_os = "linux")]
+            Self::FsType(fs_type) => fs_type.matches(entry),
+            Self::XDev => true,
+            Self::Empty => entry.is_empty(),
+            Self::Prune => {
+                entry.prune();
+                true
+            }
+            Self::Depth => true,
+            Self::Print(print) => print.run(entry),
+            Self::Gather(files) => files.gather(entry),
+            Self::Keep(top) => top.keep(entry),
+            Self::Usage(usage) => usage.tally(entry),
+            Self::Delete => action::delete(entry),
+            Self::Exec(exec) => exec.run(entry),
+            Self::Not(expr) => !expr.eval(entry),
+            Self::And(left, right) => left.eval(entry) && right.eval(entry),
+            Self::Or(left, right) => left.eval(entry) || right.eval(entry),
+        }
+    }
+
+    /// run the commands of `-exec ... +` on the paths they still hold and flush the output of
+    /// the printing actions, once all paths are walked
+    pub fn finish(&mut self) {
+        match self {
+            Self::Print(print) => print.finish(),
+            Self::Exec(exec) => exec.finish(),
+            Self::Usage(usage) => usage.finish(),
+            Self::Not(expr) => expr.finish(),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.finish();
+                right.finish();
+            }
+            _ => {}
+        }
+    }
+
+    /// search binary files as well with `-contains` and `-icontains`
+    pub fn include_binary(&mut self) {
+        match self {
+            Self::Contains(contains) => contains.include_binary(),
+            Self::Not(expr) => expr.include_binary(),
+            Self::And(left, right) | Self::Or(left, right) => {
+                left.include_binary();
+                right.include_binary();
+            }
+            _ => {}
+        }
+    }
+
+    /// an expression matching when any of the given expressions does, if there are any
+    pub fn any(exprs: impl IntoIterator<Item = Expr>) -> Option<Expr> {
+        exprs
+            .into_iter()
+            .reduce(|left, right| Self::Or(Box::new(left), Box::new(right)))
+    }
+
+    /// an expression matching when all of the given expressions do, if there are any
+    pub fn all(exprs: impl IntoIterator<Item = Expr>) -> Option<Expr> {
+        exprs
+            .into_iter()
+            .reduce(|left, right| Self::And(Box::new(left), Box::new(right)))
+    }
+}
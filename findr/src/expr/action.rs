@@ -0,0 +1,340 @@
+use super::format::Format;
+use super::json::{self, Field};
+use crate::entry::Entry;
+use crate::{output, translate_error};
+use std::ffi::{OsStr, OsString};
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufWriter, Write};
+use std::mem;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::{Arc, Mutex};
+
+/// what is left of ARG_MAX for the environment to grow after a batch is sized
+const ARG_MAX_HEADROOM: usize = 2048;
+/// the limit of the Windows command line, also used when the system does not report ARG_MAX
+const ARG_MAX_FALLBACK: usize = 32 * 1024;
+
+/// How `-print` and its variants write an entry
+#[derive(Debug, Clone, Default)]
+pub enum Output {
+    /// `-print`: the path followed by a newline
+    #[default]
+    Line,
+    /// `-print0`: the path followed by a NUL, for `xargs -0` and paths holding newlines
+    Nul,
+    /// `-printf FORMAT`
+    Format(Format),
+    /// `--format ndjson`: a JSON object holding the fields, followed by a newline
+    Json(Vec<Field>),
+}
+
+/// Where `-print` and its variants write to
+#[derive(Debug, Clone, Default)]
+pub enum Sink {
+    #[default]
+    Stdout,
+    /// the file of `-fprint FILE` and the like, shared by the primaries naming the same file
+    File(PathBuf, Arc<Mutex<BufWriter<File>>>),
+}
+
+/// `-print`, `-print0`, `-printf`, `-fprint`, `-fprint0` or `-fprintf`. Paths are written as
+/// the bytes they are made of, so that names which are not UTF-8 come out unchanged
+#[derive(Debug, Clone, Default)]
+pub struct Print {
+    output: Output,
+    sink: Sink,
+}
+
+impl Print {
+    pub fn new(output: Output, sink: Sink) -> Self {
+        Self { output, sink }
+    }
+
+    /// write the entry, which always matches
+    pub fn run(&mut self, entry: &Entry) -> bool {
+        let bytes = match &self.output {
+            Output::Line | Output::Nul => {
+                let mut bytes = entry.path().as_os_str().as_encoded_bytes().to_vec();
+                bytes.push(if matches!(self.output, Output::Nul) {
+                    b'\0'
+                } else {
+                    b'\n'
+                });
+                bytes
+            }
+            Output::Format(format) => format.render(entry),
+            Output::Json(fields) => json::render(entry, fields),
+        };
+        let res = match &self.sink {
+            Sink::Stdout => output::print(&bytes),
+            Sink::File(_, file) => lock(file).write_all(&bytes),
+        };
+        if let Err(e) = res {
+            self.report(e);
+        }
+        true
+    }
+
+    /// write out what is left in the buffer of the file
+    pub fn finish(&mut self) {
+        let res = match &self.sink {
+            Sink::Stdout => output::flush(),
+            Sink::File(_, file) => lock(file).flush(),
+        };
+        if let Err(e) = res {
+            self.report(e);
+        }
+    }
+
+    fn report(&self, e: io::Error) {
+        let name = match &self.sink {
+            Sink::Stdout => "standard output".into(),
+            Sink::File(path, _) => path.to_string_lossy(),
+        };
+        output::report(translate_error(Box::new(e), &name));
+    }
+}
+
+/// the writer of the file, even if a thread writing to it panicked
+fn lock(file: &Mutex<BufWriter<File>>) -> std::sync::MutexGuard<'_, BufWriter<File>> {
+    file.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// A command run by `-exec`, `-execdir` or `-ok`
+#[derive(Debug, Clone)]
+pub struct Exec {
+    /// the command and its arguments, in which `{}` stands for the path of the entry
+    command: Vec<String>,
+    /// whether the paths are gathered to run the command on as many at once as possible, as
+    /// `-exec ... {} +` does. The command then holds the arguments before the `{}`
+    batch: bool,
+    /// whether the command runs in the directory of the entry, on `./` and its file name
+    in_dir: bool,
+    /// whether to ask before running the command
+    prompt: bool,
+    /// the number of bytes the paths of a batch may take
+    limit: usize,
+    pending: Vec<OsString>,
+    pending_dir: Option<PathBuf>,
+    pending_size: usize,
+}
+
+impl Exec {
+    pub fn new(command: Vec<String>, batch: bool, in_dir: bool, prompt: bool) -> Self {
+        let limit = batch_limit(&command);
+        Self {
+            command,
+            batch,
+            in_dir,
+            prompt,
+            limit,
+            pending: vec![],
+            pending_dir: None,
+            pending_size: 0,
+        }
+    }
+
+    /// run the command on the entry, which matches when the command succeeds. A batched
+    /// command always matches, and only runs once enough paths are gathered
+    pub fn run(&mut self, entry: &Entry) -> bool {
+        let (dir, path) = self.target(entry);
+        if !self.batch {
+            let args = self
+                .command
+                .iter()
+                .map(|arg| replace_braces(arg, &path))
+                .collect::<Vec<_>>();
+            if self.prompt && !confirm(&args) {
+                return false;
+            }
+            return run_command(&args, dir.as_deref(), false);
+        }
+
+        let size = arg_size(&path);
+        if !self.pending.is_empty()
+            && (self.pending_dir != dir || self.pending_size + size > self.limit)
+        {
+            self.flush();
+        }
+        self.pending_dir = dir;
+        self.pending_size += size;
+        self.pending.push(path);
+        true
+    }
+
+    /// run the command on the paths still gathered for a batch
+    pub fn finish(&mut self) {
+        if !self.pending.is_empty() {
+            self.flush();
+        }
+    }
+
+    fn flush(&mut self) {
+        let args = self
+            .command
+            .iter()
+            .map(OsString::from)
+            .chain(mem::take(&mut self.pending))
+            .collect::<Vec<_>>();
+        // nothing tests the result of a batch, so its failure is reported instead
+        run_command(&args, self.pending_dir.take().as_deref(), true);
+        self.pending_size = 0;
+    }
+
+    /// the directory to run the command in, if not the current one, and the path to give it
+    fn target(&self, entry: &Entry) -> (Option<PathBuf>, OsString) {
+        if !self.in_dir {
+            return (None, entry.path().as_os_str().to_owned());
+        }
+        let dir = match entry.path().parent() {
+            Some(parent) if parent != Path::new("") => parent.to_path_buf(),
+            _ => PathBuf::from("."),
+        };
+        let mut path = OsString::from("./");
+        path.push(entry.file_name());
+        (Some(dir), path)
+    }
+}
+
+/// replace each `{}` in the argument with the path
+fn replace_braces(arg: &str, path: &OsStr) -> OsString {
+    let mut replaced = OsString::new();
+    let mut parts = arg.split("{}");
+    if let Some(first) = parts.next() {
+        replaced.push(first);
+    }
+    for part in parts {
+        replaced.push(path);
+        replaced.push(part);
+    }
+    replaced
+}
+
+/// ask on standard error whether to run the command, reading the answer from standard input
+fn confirm(args: &[OsString]) -> bool {
+    // the threads of a parallel walk ask one at a time
+    static PROMPT: Mutex<()> = Mutex::new(());
+    let _prompt = PROMPT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+    let command = args
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .collect::<Vec<_>>();
+    let _ = output::flush();
+    eprint!(
+        "< {} ... {} > ? ",
+        command[0],
+        command.last().expect("command has an argument")
+    );
+    let _ = io::stderr().flush();
+    let mut answer = String::new();
+    match io::stdin().lock().read_line(&mut answer) {
+        Ok(_) => answer.trim_start().starts_with(['y', 'Y']),
+        Err(_) => false,
+    }
+}
+
+/// run the command, reporting why it could not run. A command killed by a signal, or failing
+/// when `report_failure` is set, is reported as well
+fn run_command(args: &[impl AsRef<OsStr>], dir: Option<&Path>, report_failure: bool) -> bool {
+    let program = args[0].as_ref();
+    let mut command = Command::new(program);
+    command.args(&args[1..]);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let name = program.to_string_lossy();
+    // the command writes to the same standard output, after the entries printed so far
+    let _ = output::flush();
+    match command.status() {
+        Ok(status) if status.success() => true,
+        Ok(status) if status.code().is_some() && !report_failure => false,
+        Ok(status) => {
+            let e = io::Error::other(format!("failed with {}", status));
+            output::report(translate_error(Box::new(e), &name));
+            false
+        }
+        Err(e) => {
+            output::report(translate_error(Box::new(e), &name));
+            false
+        }
+    }
+}
+
+/// delete the entry, which matches when it is gone. Directories are only deleted once empty,
+/// which is why `-delete` walks the contents of directories first
+pub fn delete(entry: &Entry) -> bool {
+    let path = entry.path();
+    // as find, leave the starting directory alone
+    if path == Path::new(".") {
+        return true;
+    }
+    let res = if entry.file_type().is_dir() {
+        fs::remove_dir(path)
+    } else {
+        fs::remove_file(path)
+    };
+    match res {
+        Ok(()) => true,
+        Err(e) => {
+            output::report(translate_error(Box::new(e), &path.to_string_lossy()));
+            false
+        }
+    }
+}
+
+/// the space an argument takes in the arguments passed to a command: its bytes, the NUL
+/// ending it and the pointer to it
+fn arg_size(arg: impl AsRef<OsStr>) -> usize {
+    arg.as_ref().len() + 1 + mem::size_of::<usize>()
+}
+
+/// how many bytes of paths a batch of the command may hold without exceeding ARG_MAX
+fn batch_limit(command: &[String]) -> usize {
+    let environment = std::env::vars_os()
+        .map(|(key, value)| arg_size(&key) + value.len() + 1)
+        .sum::<usize>();
+    let command = command.iter().map(arg_size).sum::<usize>();
+    arg_max()
+        .saturating_sub(environment + command + ARG_MAX_HEADROOM)
+        // always leave room for one path, even if the command then fails with E2BIG
+        .max(1)
+}
+
+#[cfg(unix)]
+fn arg_max() -> usize {
+    // SAFETY: sysconf only reads a system limit
+    let max = unsafe { libc::sysconf(libc::_SC_ARG_MAX) };
+    usize::try_from(max).unwrap_or(ARG_MAX_FALLBACK)
+}
+
+#[cfg(not(unix))]
+fn arg_max() -> usize {
+    ARG_MAX_FALLBACK
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replace_braces_with_path() {
+        let path = OsStr::new("a/b.csv");
+        assert_eq!(replace_braces("{}", path), "a/b.csv");
+        assert_eq!(replace_braces("--in={}.bak", path), "--in=a/b.csv.bak");
+        assert_eq!(replace_braces("{}:{}", path), "a/b.csv:a/b.csv");
+        assert_eq!(replace_braces("echo", path), "echo");
+    }
+
+    #[test]
+    fn leave_room_for_environment_in_batches() {
+        let limit = batch_limit(&["echo".to_string()]);
+        assert!(limit > 0, "leaves room for paths");
+        assert!(
+            limit < arg_max(),
+            "leaves room for the command and the environment"
+        );
+    }
+}
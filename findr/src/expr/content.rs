@@ -0,0 +1,133 @@
+use crate::entry::Entry;
+use crate::{output, translate_error};
+use regex::bytes::{Regex, RegexBuilder};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader};
+
+/// the bytes at the start of a file checked for a NUL byte before searching it, as grep does
+const BINARY_CHECK_SIZE: usize = 8192;
+
+/// `-contains PATTERN` or `-icontains PATTERN`: a line of the regular file matches the regular
+/// expression. A file holding a NUL byte in its first 8 KiB, or before any matching line, is
+/// taken as binary, and does not match unless binary files are searched too
+#[derive(Debug, Clone)]
+pub struct Contains {
+    regex: Regex,
+    binary: bool,
+}
+
+impl Contains {
+    pub fn new(pattern: &str, case_insensitive: bool) -> Result<Self, regex::Error> {
+        let regex = RegexBuilder::new(pattern)
+            .case_insensitive(case_insensitive)
+            .build()?;
+        Ok(Self {
+            regex,
+            binary: false,
+        })
+    }
+
+    /// search binary files as well, as `--binary` asks
+    pub fn include_binary(&mut self) {
+        self.binary = true;
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        if !entry.file_type().is_file() {
+            return false;
+        }
+        let reader = |file| BufReader::with_capacity(BINARY_CHECK_SIZE, file);
+        match File::open(entry.path()).and_then(|file| self.search(reader(file))) {
+            Ok(found) => found,
+            Err(e) => {
+                let pathname = entry.path().to_string_lossy();
+                output::report(translate_error(Box::new(e), &pathname));
+                false
+            }
+        }
+    }
+
+    /// read the lines up to the first one matching, or to the first NUL byte unless searching
+    /// binary files. Unless searching them, the first block the reader buffers is checked for a
+    /// NUL byte before any line is matched
+    fn search(&self, mut reader: impl BufRead) -> io::Result<bool> {
+        if !self.binary && reader.fill_buf()?.contains(&0) {
+            return Ok(false);
+        }
+        let mut line = vec![];
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(false);
+            }
+            if !self.binary && line.contains(&0) {
+                return Ok(false);
+            }
+            // match the line without its ending, so that `$` matches at the end of every line
+            let text = line.strip_suffix(b"\n").unwrap_or(&line);
+            let text = text.strip_suffix(b"\r").unwrap_or(text);
+            if self.regex.is_match(text) {
+                return Ok(true);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn search(pattern: &str, contents: &[u8]) -> bool {
+        Contains::new(pattern, false)
+            .unwrap()
+            .search(BufReader::with_capacity(BINARY_CHECK_SIZE, contents))
+            .unwrap()
+    }
+
+    #[test]
+    fn match_lines() {
+        assert!(search("b.d", b"abc\nabcd\n"));
+        assert!(
+            search("^abcd$", b"abc\nabcd"),
+            "matches lines, not the file"
+        );
+        assert!(
+            search("^abc$", b"abc\nabcd\n"),
+            "matches lines without their newline"
+        );
+        assert!(
+            search("^abc$", b"abc\r\nabcd\r\n"),
+            "matches lines without their CRLF"
+        );
+        assert!(
+            !search("c\\nab", b"abc\nabcd\n"),
+            "does not match across lines"
+        );
+        assert!(!search("x", b""));
+        assert!(
+            search("caf", b"caf\xe9\n"),
+            "searches lines which are not UTF-8"
+        );
+        assert!(Contains::new("ABC", true)
+            .unwrap()
+            .search(&b"abc"[..])
+            .unwrap());
+    }
+
+    #[test]
+    fn skip_binary_files() {
+        assert!(!search("needle", b"\0\nneedle\n"));
+        assert!(
+            !search("needle", b"needle\n\0"),
+            "checks the first block before matching"
+        );
+        let late_nul = [&b"needle\n"[..], &[b'x'; BINARY_CHECK_SIZE], b"\0"].concat();
+        assert!(
+            search("needle", &late_nul),
+            "stops at the first match after the first block"
+        );
+        let mut contains = Contains::new("needle", false).unwrap();
+        contains.include_binary();
+        assert!(contains.search(&b"\0\nneedle\n"[..]).unwrap());
+    }
+}
@@ -0,0 +1,608 @@
+use crate::entry::Entry;
+use std::fs::{self, FileType, Metadata};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+/// The format of `-printf`, made of text, `\` escapes and `%` directives
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Format {
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Part {
+    Text(Vec<u8>),
+    Directive(Directive),
+    /// `\c`: nothing more is printed for the entry
+    Stop,
+}
+
+/// A `%` directive, with the flags, width and precision of printf
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Directive {
+    left_align: bool,
+    zero_pad: bool,
+    width: usize,
+    precision: Option<usize>,
+    conversion: char,
+    /// the field of the time to print for `%A`, `%C` and `%T`
+    time_field: Option<char>,
+}
+
+impl Format {
+    /// parse the format, or describe what is wrong with it
+    pub fn parse(format: &str) -> Result<Self, String> {
+        let mut parts = vec![];
+        let mut text = vec![];
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some('c') => {
+                        Self::push_text(&mut parts, &mut text);
+                        parts.push(Part::Stop);
+                    }
+                    Some(c @ '0'..='7') => {
+                        let mut code = c.to_digit(8).expect("is an octal digit");
+                        for _ in 0..2 {
+                            match chars.next_if(|c| matches!(c, '0'..='7')) {
+                                Some(c) => code = code * 8 + c.to_digit(8).expect("is octal"),
+                                None => break,
+                            }
+                        }
+                        text.push(u8::try_from(code).map_err(|_| {
+                            format!("invalid escape '\\{:o}' in '-printf' format", code)
+                        })?);
+                    }
+                    Some(c) => text.push(match c {
+                        'n' => b'\n',
+                        't' => b'\t',
+                        'r' => b'\r',
+                        'a' => 0x07,
+                        'b' => 0x08,
+                        'f' => 0x0c,
+                        'v' => 0x0b,
+                        '\\' => b'\\',
+                        c => return Err(format!("unknown escape '\\{}' in '-printf' format", c)),
+                    }),
+                    None => text.push(b'\\'),
+                },
+                '%' => {
+                    if chars.next_if_eq(&'%').is_some() {
+                        text.push(b'%');
+                        continue;
+                    }
+                    let mut directive = Directive {
+                        left_align: false,
+                        zero_pad: false,
+                        width: 0,
+                        precision: None,
+                        conversion: ' ',
+                        time_field: None,
+                    };
+                    while let Some(flag) = chars.next_if(|c| matches!(c, '-' | '0')) {
+                        directive.left_align |= flag == '-';
+                        directive.zero_pad |= flag == '0';
+                    }
+                    directive.width = Self::parse_number(&mut chars);
+                    if chars.next_if_eq(&'.').is_some() {
+                        directive.precision = Some(Self::parse_number(&mut chars));
+                    }
+                    let conversion = chars
+                        .next()
+                        .ok_or("incomplete directive '%' at the end of '-printf' format")?;
+                    if !"pfhPHsmMugUGdyYlinkbatcACT".contains(conversion) {
+                        return Err(format!(
+                            "unknown directive '%{}' in '-printf' format",
+                            conversion
+                        ));
+                    }
+                    directive.conversion = conversion;
+                    if matches!(conversion, 'A' | 'C' | 'T') {
+                        let field = chars.next().filter(|c| "@YymdHMSFT+ab".contains(*c));
+                        directive.time_field = Some(field.ok_or_else(|| {
+                            format!(
+                                "unknown time field after '%{}' in '-printf' format",
+                                conversion
+                            )
+                        })?);
+                    }
+                    Self::push_text(&mut parts, &mut text);
+                    parts.push(Part::Directive(directive));
+                }
+                c => text.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        Self::push_text(&mut parts, &mut text);
+        Ok(Self { parts })
+    }
+
+    fn push_text(parts: &mut Vec<Part>, text: &mut Vec<u8>) {
+        if !text.is_empty() {
+            parts.push(Part::Text(std::mem::take(text)));
+        }
+    }
+
+    fn parse_number(chars: &mut std::iter::Peekable<std::str::Chars>) -> usize {
+        let mut number = 0;
+        while let Some(digit) = chars.next_if(char::is_ascii_digit) {
+            number = number * 10 + digit.to_digit(10).expect("is a digit") as usize;
+        }
+        number
+    }
+
+    /// the bytes printed for the entry, with its path and names exactly as they are
+    pub fn render(&self, entry: &Entry) -> Vec<u8> {
+        let mut out = vec![];
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.extend_from_slice(text),
+                Part::Directive(directive) => directive.render(entry, &mut out),
+                Part::Stop => break,
+            }
+        }
+        out
+    }
+}
+
+impl Directive {
+    fn render(&self, entry: &Entry, out: &mut Vec<u8>) {
+        let value = self.value(entry);
+        let value = match self.precision {
+            Some(precision) => truncate(value, precision),
+            None => value,
+        };
+        let len = String::from_utf8(value.clone()).map_or(value.len(), |s| s.chars().count());
+        let padding = self.width.saturating_sub(len);
+        if self.left_align {
+            out.extend_from_slice(&value);
+            out.resize(out.len() + padding, b' ');
+        } else if self.zero_pad && value.iter().all(u8::is_ascii_digit) {
+            out.resize(out.len() + padding, b'0');
+            out.extend_from_slice(&value);
+        } else {
+            out.resize(out.len() + padding, b' ');
+            out.extend_from_slice(&value);
+        }
+    }
+
+    fn value(&self, entry: &Entry) -> Vec<u8> {
+        let path = entry.path();
+        let metadata = || entry.metadata();
+        let number =
+            |f: fn(&Metadata) -> u64| metadata().map_or(vec![], |m| f(m).to_string().into_bytes());
+        match self.conversion {
+            'p' => bytes(path.as_os_str()),
+            'f' => bytes(entry.file_name()),
+            'h' => match path.parent() {
+                Some(parent) if !parent.as_os_str().is_empty() => bytes(parent.as_os_str()),
+                _ => b".".to_vec(),
+            },
+            'H' => bytes(entry.root().as_os_str()),
+            'P' => path
+                .strip_prefix(entry.root())
+                .map_or(vec![], |rest| bytes(rest.as_os_str())),
+            's' => number(Metadata::len),
+            'k' => number(|m| blocks(m).div_ceil(2)),
+            'b' => number(blocks),
+            'd' => entry.depth().to_string().into_bytes(),
+            'y' => vec![type_char(entry.file_type()) as u8],
+            'Y' => match fs::metadata(path) {
+                Ok(target) => vec![type_char(target.file_type()) as u8],
+                // as find does, a broken link is reported as N, and a loop as L
+                Err(e) if e.raw_os_error() == Some(LOOP_ERROR) => b"L".to_vec(),
+                Err(_) if entry.file_type().is_symlink() => b"N".to_vec(),
+                Err(_) => b"?".to_vec(),
+            },
+            'l' => match entry.file_type().is_symlink() {
+                true => fs::read_link(path).map_or(vec![], |target| bytes(target.as_os_str())),
+                false => vec![],
+            },
+            'm' => metadata().map_or(vec![], |m| format!("{:o}", mode(m) & 0o7777).into_bytes()),
+            'M' => metadata().map_or(vec![], |m| {
+                symbolic_mode(entry.file_type(), mode(m)).into_bytes()
+            }),
+            'i' => number(inode),
+            'n' => number(links),
+            'U' => number(|m| owner(m).0.into()),
+            'G' => number(|m| owner(m).1.into()),
+            'u' => metadata().map_or(vec![], |m| user_name(owner(m).0)),
+            'g' => metadata().map_or(vec![], |m| group_name(owner(m).1)),
+            'a' | 't' | 'c' => metadata()
+                .and_then(|m| time_of(m, self.conversion))
+                .map_or(vec![], |time| ctime(time).into_bytes()),
+            'A' | 'T' | 'C' => {
+                let field = self.time_field.expect("time directives have a field");
+                let conversion = match self.conversion {
+                    'A' => 'a',
+                    'T' => 't',
+                    _ => 'c',
+                };
+                metadata()
+                    .and_then(|m| time_of(m, conversion))
+                    .map_or(vec![], |time| time_field(time, field).into_bytes())
+            }
+            _ => unreachable!("directives are checked when parsed"),
+        }
+    }
+}
+
+fn bytes(s: &std::ffi::OsStr) -> Vec<u8> {
+    s.as_encoded_bytes().to_vec()
+}
+
+/// keep the first `precision` characters of the value, or bytes if it is not UTF-8
+fn truncate(mut value: Vec<u8>, precision: usize) -> Vec<u8> {
+    match std::str::from_utf8(&value) {
+        Ok(s) => s.chars().take(precision).collect::<String>().into_bytes(),
+        Err(_) => {
+            value.truncate(precision);
+            value
+        }
+    }
+}
+
+/// the letter find uses for the file type, as in `-type`
+pub fn type_char(file_type: FileType) -> char {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if file_type.is_block_device() {
+            return 'b';
+        } else if file_type.is_char_device() {
+            return 'c';
+        } else if file_type.is_fifo() {
+            return 'p';
+        } else if file_type.is_socket() {
+            return 's';
+        }
+    }
+    if file_type.is_dir() {
+        'd'
+    } else if file_type.is_symlink() {
+        'l'
+    } else if file_type.is_file() {
+        'f'
+    } else {
+        'U'
+    }
+}
+
+/// the permissions as `ls -l` shows them, such as `-rw-r--r--`
+fn symbolic_mode(file_type: FileType, mode: u32) -> String {
+    let mut symbolic = String::with_capacity(10);
+    symbolic.push(match type_char(file_type) {
+        'f' | 'U' => '-',
+        c => c,
+    });
+    for (shift, special, special_char) in [(6, 0o4000, 's'), (3, 0o2000, 's'), (0, 0o1000, 't')] {
+        let bits = mode >> shift;
+        symbolic.push(if bits & 0o4 != 0 { 'r' } else { '-' });
+        symbolic.push(if bits & 0o2 != 0 { 'w' } else { '-' });
+        symbolic.push(match (bits & 0o1 != 0, mode & special != 0) {
+            (true, true) => special_char,
+            (false, true) => special_char.to_ascii_uppercase(),
+            (true, false) => 'x',
+            (false, false) => '-',
+        });
+    }
+    symbolic
+}
+
+#[cfg(unix)]
+const LOOP_ERROR: i32 = libc::ELOOP;
+#[cfg(not(unix))]
+const LOOP_ERROR: i32 = -1;
+
+#[cfg(unix)]
+mod unix {
+    pub use std::os::unix::fs::MetadataExt;
+}
+
+#[cfg(unix)]
+pub fn mode(metadata: &Metadata) -> u32 {
+    unix::MetadataExt::mode(metadata)
+}
+
+#[cfg(not(unix))]
+pub fn mode(metadata: &Metadata) -> u32 {
+    if metadata.permissions().readonly() {
+        0o444
+    } else {
+        0o666
+    }
+}
+
+#[cfg(unix)]
+fn blocks(metadata: &Metadata) -> u64 {
+    unix::MetadataExt::blocks(metadata)
+}
+
+#[cfg(not(unix))]
+fn blocks(metadata: &Metadata) -> u64 {
+    metadata.len().div_ceil(512)
+}
+
+#[cfg(unix)]
+fn inode(metadata: &Metadata) -> u64 {
+    unix::MetadataExt::ino(metadata)
+}
+
+#[cfg(not(unix))]
+fn inode(_: &Metadata) -> u64 {
+    0
+}
+
+#[cfg(unix)]
+fn links(metadata: &Metadata) -> u64 {
+    unix::MetadataExt::nlink(metadata)
+}
+
+#[cfg(not(unix))]
+fn links(_: &Metadata) -> u64 {
+    1
+}
+
+/// the user and group ids owning the entry
+#[cfg(unix)]
+fn owner(metadata: &Metadata) -> (u32, u32) {
+    (
+        unix::MetadataExt::uid(metadata),
+        unix::MetadataExt::gid(metadata),
+    )
+}
+
+#[cfg(not(unix))]
+fn owner(_: &Metadata) -> (u32, u32) {
+    (0, 0)
+}
+
+/// the name of the user, or the id when the user has none
+fn user_name(uid: u32) -> Vec<u8> {
+    #[cfg(unix)]
+    if let Some(user) = uzers::get_user_by_uid(uid) {
+        return bytes(user.name());
+    }
+    uid.to_string().into_bytes()
+}
+
+/// the name of the group, or the id when the group has none
+fn group_name(gid: u32) -> Vec<u8> {
+    #[cfg(unix)]
+    if let Some(group) = uzers::get_group_by_gid(gid) {
+        return bytes(group.name());
+    }
+    gid.to_string().into_bytes()
+}
+
+/// the time of last access (`a`), modification (`t`) or status change (`c`)
+fn time_of(metadata: &Metadata, conversion: char) -> Option<SystemTime> {
+    match conversion {
+        'a' => metadata.accessed().ok(),
+        #[cfg(unix)]
+        'c' => {
+            let secs = unix::MetadataExt::ctime(metadata);
+            let nanos = unix::MetadataExt::ctime_nsec(metadata);
+            let since_epoch = std::time::Duration::new(secs.unsigned_abs(), nanos as u32);
+            if secs < 0 {
+                UNIX_EPOCH.checked_sub(since_epoch)
+            } else {
+                UNIX_EPOCH.checked_add(since_epoch)
+            }
+        }
+        _ => metadata.modified().ok(),
+    }
+}
+
+/// A time broken down into the local date and time
+struct LocalTime {
+    year: i64,
+    /// 1 to 12
+    month: u32,
+    day: u32,
+    hour: u32,
+    minute: u32,
+    second: u32,
+    /// 0 for Sunday to 6 for Saturday
+    weekday: u32,
+}
+
+/// the time in the format of ctime(3), such as `Wed Jun 26 12:00:00 2024`
+fn ctime(time: SystemTime) -> String {
+    let t = local_time(time);
+    format!(
+        "{} {} {:>2} {:02}:{:02}:{:02} {}",
+        WEEKDAYS[t.weekday as usize],
+        MONTHS[t.month as usize - 1],
+        t.day,
+        t.hour,
+        t.minute,
+        t.second,
+        t.year
+    )
+}
+
+/// a field of the time, as the `%T` directives of find print it
+fn time_field(time: SystemTime, field: char) -> String {
+    if field == '@' {
+        return match time.duration_since(UNIX_EPOCH) {
+            Ok(since) => format!("{}.{:09}0", since.as_secs(), since.subsec_nanos()),
+            Err(e) => format!("-{}", e.duration().as_secs_f64()),
+        };
+    }
+    let t = local_time(time);
+    match field {
+        'Y' => t.year.to_string(),
+        'y' => format!("{:02}", t.year.rem_euclid(100)),
+        'm' => format!("{:02}", t.month),
+        'd' => format!("{:02}", t.day),
+        'H' => format!("{:02}", t.hour),
+        'M' => format!("{:02}", t.minute),
+        'S' => format!("{:02}", t.second),
+        'F' => format!("{}-{:02}-{:02}", t.year, t.month, t.day),
+        'T' => format!("{:02}:{:02}:{:02}", t.hour, t.minute, t.second),
+        '+' => format!(
+            "{}-{:02}-{:02}+{:02}:{:02}:{:02}",
+            t.year, t.month, t.day, t.hour, t.minute, t.second
+        ),
+        'a' => WEEKDAYS[t.weekday as usize].to_string(),
+        _ => MONTHS[t.month as usize - 1].to_string(),
+    }
+}
+
+fn unix_seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs_f64().ceil() as i64),
+    }
+}
+
+#[cfg(unix)]
+fn local_time(time: SystemTime) -> LocalTime {
+    let secs = unix_seconds(time) as libc::time_t;
+    // SAFETY: tm is plain data that localtime_r fills in, and both pointers are valid
+    let tm = unsafe {
+        let mut tm = std::mem::zeroed::<libc::tm>();
+        if libc::localtime_r(&secs, &mut tm).is_null() {
+            return utc_time(unix_seconds(time));
+        }
+        tm
+    };
+    LocalTime {
+        year: i64::from(tm.tm_year) + 1900,
+        month: tm.tm_mon as u32 + 1,
+        day: tm.tm_mday as u32,
+        hour: tm.tm_hour as u32,
+        minute: tm.tm_min as u32,
+        second: tm.tm_sec as u32,
+        weekday: tm.tm_wday as u32,
+    }
+}
+
+#[cfg(not(unix))]
+fn local_time(time: SystemTime) -> LocalTime {
+    utc_time(unix_seconds(time))
+}
+
+/// the UTC date and time of the seconds since the epoch, using the days from civil algorithm
+/// of Howard Hinnant
+fn utc_time(secs: i64) -> LocalTime {
+    let days = secs.div_euclid(86_400);
+    let seconds = secs.rem_euclid(86_400) as u32;
+    let shifted = days + 719_468;
+    let era = shifted.div_euclid(146_097);
+    let day_of_era = shifted.rem_euclid(146_097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = (day_of_year - (153 * month_index + 2) / 5 + 1) as u32;
+    let month = if month_index < 10 {
+        month_index + 3
+    } else {
+        month_index - 9
+    } as u32;
+    let year = year_of_era + era * 400 + i64::from(month <= 2);
+    LocalTime {
+        year,
+        month,
+        day,
+        hour: seconds / 3600,
+        minute: seconds / 60 % 60,
+        second: seconds % 60,
+        // the epoch was a Thursday
+        weekday: (days + 4).rem_euclid(7) as u32,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn directive(conversion: char) -> Directive {
+        Directive {
+            left_align: false,
+            zero_pad: false,
+            width: 0,
+            precision: None,
+            conversion,
+            time_field: None,
+        }
+    }
+
+    #[test]
+    fn parse_formats() {
+        assert_eq!(
+            Format::parse("%p\\t%-5.2s%%\\n\\101\\c").unwrap().parts,
+            [
+                Part::Directive(directive('p')),
+                Part::Text(b"\t".to_vec()),
+                Part::Directive(Directive {
+                    left_align: true,
+                    width: 5,
+                    precision: Some(2),
+                    ..directive('s')
+                }),
+                Part::Text(b"%\nA".to_vec()),
+                Part::Stop,
+            ]
+        );
+        assert_eq!(
+            Format::parse("%T@ %CY").unwrap().parts,
+            [
+                Part::Directive(Directive {
+                    time_field: Some('@'),
+                    ..directive('T')
+                }),
+                Part::Text(b" ".to_vec()),
+                Part::Directive(Directive {
+                    time_field: Some('Y'),
+                    ..directive('C')
+                }),
+            ]
+        );
+        assert_eq!(
+            Format::parse("%q"),
+            Err("unknown directive '%q' in '-printf' format".to_string())
+        );
+        assert_eq!(
+            Format::parse("%Tq"),
+            Err("unknown time field after '%T' in '-printf' format".to_string())
+        );
+        assert_eq!(
+            Format::parse("%5"),
+            Err("incomplete directive '%' at the end of '-printf' format".to_string())
+        );
+        assert_eq!(
+            Format::parse("\\e"),
+            Err("unknown escape '\\e' in '-printf' format".to_string())
+        );
+    }
+
+    #[test]
+    fn convert_epoch_to_civil_time() {
+        let t = utc_time(0);
+        assert_eq!((t.year, t.month, t.day, t.weekday), (1970, 1, 1, 4));
+        let t = utc_time(1_719_403_200 + 3661);
+        assert_eq!(
+            (t.year, t.month, t.day, t.hour, t.minute, t.second, t.weekday),
+            (2024, 6, 26, 13, 1, 1, 3)
+        );
+        let t = utc_time(-86_400);
+        assert_eq!((t.year, t.month, t.day, t.weekday), (1969, 12, 31, 3));
+    }
+
+    #[test]
+    fn show_permissions_like_ls() {
+        let file_type = fs::metadata("Cargo.toml").unwrap().file_type();
+        assert_eq!(symbolic_mode(file_type, 0o644), "-rw-r--r--");
+        assert_eq!(symbolic_mode(file_type, 0o4755), "-rwsr-xr-x");
+        assert_eq!(symbolic_mode(file_type, 0o1644), "-rw-r--r-T");
+        let dir_type = fs::metadata("src").unwrap().file_type();
+        assert_eq!(symbolic_mode(dir_type, 0o2750), "drwxr-s---");
+    }
+}
@@ -0,0 +1,118 @@
+use super::format::{mode, type_char};
+use crate::entry::Entry;
+use base64::prelude::{Engine, BASE64_STANDARD};
+use clap::ValueEnum;
+use serde_json::{json, Value};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// A field of the JSON object written for each entry by `--format ndjson`
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Field {
+    /// the path, as a string, or as the base64 of its bytes when it is not UTF-8
+    Path,
+    /// the letter of the file type, as in -type
+    Type,
+    /// the size in bytes
+    Size,
+    /// the modification time, in seconds since the Unix epoch
+    Mtime,
+    /// the permissions in octal, as %m prints them
+    Mode,
+    /// the depth below the path searched
+    Depth,
+}
+
+impl Field {
+    /// the fields written unless `--fields` chooses others
+    pub const ALL: [Self; 6] = [
+        Self::Path,
+        Self::Type,
+        Self::Size,
+        Self::Mtime,
+        Self::Mode,
+        Self::Depth,
+    ];
+}
+
+/// The JSON object of the entry holding the fields in the order given, on a line of its own.
+/// The fields needing metadata are null when it cannot be read
+pub fn render(entry: &Entry, fields: &[Field]) -> Vec<u8> {
+    let metadata = entry.metadata();
+    // written out by hand, as a JSON map would sort the fields by name
+    let members: Vec<_> = fields
+        .iter()
+        .map(|field| {
+            let (name, value) = match field {
+                Field::Path => ("path", path(entry.path())),
+                Field::Type => ("type", json!(type_char(entry.file_type()).to_string())),
+                Field::Size => ("size", json!(metadata.map(|m| m.len()))),
+                Field::Mtime => (
+                    "mtime",
+                    json!(metadata.and_then(|m| m.modified().ok()).map(seconds)),
+                ),
+                Field::Mode => (
+                    "mode",
+                    json!(metadata.map(|m| format!("{:o}", mode(m) & 0o7777))),
+                ),
+                Field::Depth => ("depth", json!(entry.depth())),
+            };
+            format!("{}:{}", json!(name), value)
+        })
+        .collect();
+    format!("{{{}}}\n", members.join(",")).into_bytes()
+}
+
+/// The path as a JSON string, or, when it is not UTF-8, as an object holding the base64 of its
+/// bytes in its `base64` field, so that no path is lost
+pub fn path(path: &Path) -> Value {
+    let bytes = path.as_os_str().as_encoded_bytes();
+    match std::str::from_utf8(bytes) {
+        Ok(path) => json!(path),
+        Err(_) => json!({ "base64": BASE64_STANDARD.encode(bytes) }),
+    }
+}
+
+/// the seconds from the Unix epoch to the time, negative before it
+fn seconds(time: SystemTime) -> i64 {
+    match time.duration_since(UNIX_EPOCH) {
+        Ok(since) => since.as_secs() as i64,
+        Err(e) => -(e.duration().as_secs() as i64),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    #[test]
+    fn encode_paths_losslessly() {
+        assert_eq!(path(Path::new("a/b c")), json!("a/b c"));
+        #[cfg(unix)]
+        {
+            use std::ffi::OsStr;
+            use std::os::unix::ffi::OsStrExt;
+            assert_eq!(
+                path(Path::new(OsStr::from_bytes(b"caf\xe9"))),
+                json!({ "base64": "Y2Fm6Q==" })
+            );
+        }
+    }
+
+    #[test]
+    fn render_fields_in_order() {
+        let entry = walkdir::WalkDir::new("src").into_iter().next();
+        let entry = Entry::from(entry.expect("src exists").expect("src is readable"));
+        assert_eq!(
+            String::from_utf8(render(&entry, &[Field::Type, Field::Path, Field::Depth])).unwrap(),
+            "{\"type\":\"d\",\"path\":\"src\",\"depth\":0}\n"
+        );
+    }
+
+    #[test]
+    fn count_seconds_from_epoch() {
+        assert_eq!(seconds(UNIX_EPOCH + Duration::from_millis(1500)), 1);
+        assert_eq!(seconds(UNIX_EPOCH - Duration::from_secs(60)), -60);
+    }
+}
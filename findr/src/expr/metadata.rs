@@ -0,0 +1,282 @@
+use std::cmp::Ordering;
+use std::fs::Metadata;
+use std::time::SystemTime;
+
+/// A number compared as find does: `+N` is more than N, `-N` less than N and `N` exactly N
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Numeric {
+    ordering: Ordering,
+    value: i64,
+}
+
+impl Numeric {
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (ordering, digits) = match arg.as_bytes().first() {
+            Some(b'+') => (Ordering::Greater, &arg[1..]),
+            Some(b'-') => (Ordering::Less, &arg[1..]),
+            _ => (Ordering::Equal, arg),
+        };
+        if !digits.starts_with(|c: char| c.is_ascii_digit()) {
+            return None;
+        }
+        let value = digits.parse().ok()?;
+        Some(Self { ordering, value })
+    }
+
+    /// a number only matching itself
+    pub fn exactly(value: i64) -> Self {
+        Self {
+            ordering: Ordering::Equal,
+            value,
+        }
+    }
+
+    pub fn matches(&self, actual: i64) -> bool {
+        actual.cmp(&self.value) == self.ordering
+    }
+}
+
+/// `-size [+-]N[bcwkMG]`: the size, rounded up to the unit, compared to N
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Size {
+    numeric: Numeric,
+    /// the number of bytes in a unit, 512 byte blocks unless another unit is given
+    unit: u64,
+}
+
+impl Size {
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (number, unit) = match arg.char_indices().last()? {
+            (i, 'b') => (&arg[..i], 512),
+            (i, 'c') => (&arg[..i], 1),
+            (i, 'w') => (&arg[..i], 2),
+            (i, 'k') => (&arg[..i], 1024),
+            (i, 'M') => (&arg[..i], 1024 * 1024),
+            (i, 'G') => (&arg[..i], 1024 * 1024 * 1024),
+            _ => (arg, 512),
+        };
+        Numeric::parse(number).map(|numeric| Self { numeric, unit })
+    }
+
+    /// as in find, a size is rounded up, so that `-size -1M` only matches empty files
+    pub fn matches(&self, len: u64) -> bool {
+        let units = i64::try_from(len.div_ceil(self.unit)).unwrap_or(i64::MAX);
+        self.numeric.matches(units)
+    }
+}
+
+/// Which of the times of an entry a time test looks at
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimeField {
+    /// last access, `-atime` and `-amin`
+    Access,
+    /// last change of the contents, `-mtime` and `-mmin`
+    Modify,
+    /// last change of the status, `-ctime` and `-cmin`
+    #[cfg(unix)]
+    Change,
+}
+
+/// `-mtime`, `-mmin` and the like: the time since the entry was last accessed or changed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Time {
+    field: TimeField,
+    numeric: Numeric,
+    /// the number of seconds in a unit, a day or a minute
+    unit: i64,
+    /// the time findr started, which ages are measured from
+    start: SystemTime,
+}
+
+impl Time {
+    pub fn new(field: TimeField, numeric: Numeric, unit: i64, start: SystemTime) -> Self {
+        Self {
+            field,
+            numeric,
+            unit,
+            start,
+        }
+    }
+
+    /// as in find, any fraction of a unit is ignored, so that `-mtime +1` matches entries
+    /// changed at least two days ago
+    pub fn matches(&self, metadata: &Metadata) -> bool {
+        let Some(time) = self.time(metadata) else {
+            return false;
+        };
+        let age = match self.start.duration_since(time) {
+            Ok(age) => i64::try_from(age.as_secs()).unwrap_or(i64::MAX),
+            Err(e) => -i64::try_from(e.duration().as_secs()).unwrap_or(i64::MAX) - 1,
+        };
+        self.numeric.matches(age.div_euclid(self.unit))
+    }
+
+    fn time(&self, metadata: &Metadata) -> Option<SystemTime> {
+        match self.field {
+            TimeField::Access => metadata.accessed().ok(),
+            TimeField::Modify => metadata.modified().ok(),
+            #[cfg(unix)]
+            TimeField::Change => {
+                use std::os::unix::fs::MetadataExt;
+                use std::time::{Duration, UNIX_EPOCH};
+
+                let secs = Duration::from_secs(metadata.ctime().unsigned_abs());
+                let nanos = Duration::from_nanos(metadata.ctime_nsec().unsigned_abs());
+                if metadata.ctime() < 0 {
+                    UNIX_EPOCH.checked_sub(secs)?.checked_add(nanos)
+                } else {
+                    UNIX_EPOCH.checked_add(secs + nanos)
+                }
+            }
+        }
+    }
+}
+
+/// How the permission bits of `-perm` are compared with those of an entry
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PermMatch {
+    /// `-perm MODE`: the permission bits are exactly the mode
+    Exact,
+    /// `-perm -MODE`: all the bits of the mode are set
+    All,
+    /// `-perm /MODE`: any of the bits of the mode is set
+    Any,
+}
+
+/// `-perm MODE`, `-perm -MODE` or `-perm /MODE`, with an octal or a symbolic mode such as
+/// `u=rw,go+r`
+#[cfg(unix)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Perm {
+    mode: u32,
+    kind: PermMatch,
+}
+
+#[cfg(unix)]
+impl Perm {
+    pub fn parse(arg: &str) -> Option<Self> {
+        let (kind, mode) = match arg.as_bytes().first()? {
+            b'-' => (PermMatch::All, &arg[1..]),
+            b'/' => (PermMatch::Any, &arg[1..]),
+            _ => (PermMatch::Exact, arg),
+        };
+        let mode = if mode.starts_with(|c: char| c.is_ascii_digit()) {
+            u32::from_str_radix(mode, 8)
+                .ok()
+                .filter(|mode| *mode <= 0o7777)?
+        } else {
+            symbolic_mode(mode)?
+        };
+        Some(Self { mode, kind })
+    }
+
+    pub fn matches(&self, mode: u32) -> bool {
+        let mode = mode & 0o7777;
+        match self.kind {
+            PermMatch::Exact => mode == self.mode,
+            PermMatch::All => mode & self.mode == self.mode,
+            // as in find, no bits to look for matches every entry
+            PermMatch::Any => self.mode == 0 || mode & self.mode != 0,
+        }
+    }
+}
+
+/// the bits of a symbolic mode such as `u=rw,go+r`, applied to no permissions at all
+#[cfg(unix)]
+fn symbolic_mode(mode: &str) -> Option<u32> {
+    let mut bits = 0;
+    for clause in mode.split(',') {
+        let who_len = clause
+            .find(|c: char| !"ugoa".contains(c))
+            .unwrap_or(clause.len());
+        let who = match clause[..who_len]
+            .chars()
+            .map(|c| match c {
+                'u' => 0o4700,
+                'g' => 0o2070,
+                'o' => 0o1007,
+                _ => 0o7777,
+            })
+            .fold(0, |who, mask| who | mask)
+        {
+            0 => 0o7777,
+            who => who,
+        };
+        let mut actions = clause[who_len..].chars().peekable();
+        actions.peek()?;
+        while let Some(op) = actions.next() {
+            let mut perms = 0;
+            while let Some(perm) = actions.next_if(|c| "rwxst".contains(*c)) {
+                perms |= match perm {
+                    'r' => 0o444,
+                    'w' => 0o222,
+                    'x' => 0o111,
+                    's' => 0o6000,
+                    _ => 0o1000,
+                };
+            }
+            match op {
+                '+' => bits |= perms & who,
+                '-' => bits &= !(perms & who),
+                '=' => bits = (bits & !who) | (perms & who),
+                _ => return None,
+            }
+        }
+    }
+    Some(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compare_numbers_like_find() {
+        let more = Numeric::parse("+2").unwrap();
+        let less = Numeric::parse("-2").unwrap();
+        let exactly = Numeric::parse("2").unwrap();
+        assert!(more.matches(3) && !more.matches(2));
+        assert!(less.matches(1) && !less.matches(2));
+        assert!(exactly.matches(2) && !exactly.matches(3));
+        assert_eq!(Numeric::parse("+-2"), None);
+        assert_eq!(Numeric::parse("two"), None);
+    }
+
+    #[test]
+    fn round_sizes_up_to_unit() {
+        assert!(Size::parse("-1M").unwrap().matches(0));
+        assert!(!Size::parse("-1M").unwrap().matches(1));
+        assert!(Size::parse("1k").unwrap().matches(1));
+        assert!(Size::parse("2").unwrap().matches(513));
+        assert!(Size::parse("+10c").unwrap().matches(11));
+        assert!(Size::parse("3w").unwrap().matches(6));
+        assert_eq!(Size::parse("10x"), None);
+        assert_eq!(Size::parse("k"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn compare_permissions() {
+        assert!(Perm::parse("644").unwrap().matches(0o100644));
+        assert!(!Perm::parse("644").unwrap().matches(0o100664));
+        assert!(Perm::parse("-220").unwrap().matches(0o664));
+        assert!(!Perm::parse("-220").unwrap().matches(0o644));
+        assert!(Perm::parse("/022").unwrap().matches(0o664));
+        assert!(!Perm::parse("/022").unwrap().matches(0o600));
+        assert!(Perm::parse("/000").unwrap().matches(0o600));
+        assert_eq!(Perm::parse("9"), None);
+        assert_eq!(Perm::parse("10000"), None);
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn parse_symbolic_modes() {
+        assert_eq!(symbolic_mode("u=rw,go=r"), Some(0o644));
+        assert_eq!(symbolic_mode("a+rx,u+w"), Some(0o755));
+        assert_eq!(symbolic_mode("=rw,o-w"), Some(0o664));
+        assert_eq!(symbolic_mode("u+s,+t"), Some(0o5000));
+        assert_eq!(symbolic_mode("u"), None);
+        assert_eq!(symbolic_mode("u*r"), None);
+    }
+}
@@ -0,0 +1,185 @@
+use crate::entry::Entry;
+use std::fs;
+use std::io;
+use std::os::unix::fs::MetadataExt;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+const MOUNTINFO: &str = "/proc/self/mountinfo";
+
+/// A file system mounted on the system, as `/proc/self/mountinfo` lists it
+#[derive(Debug, PartialEq, Eq)]
+struct Mount {
+    /// the major and minor numbers of the device the file system is on
+    device: (u32, u32),
+    mount_point: PathBuf,
+    fs_type: String,
+}
+
+/// The file systems mounted on the system, in the order they were mounted
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct Mounts {
+    mounts: Vec<Mount>,
+}
+
+impl Mounts {
+    pub fn read() -> io::Result<Self> {
+        fs::read_to_string(MOUNTINFO).map(|mountinfo| Self::parse(&mountinfo))
+    }
+
+    /// parse the lines of mountinfo, such as
+    /// `36 35 98:0 /mnt1 /mnt2 rw,noatime master:1 - ext3 /dev/root rw`, skipping any line
+    /// it cannot make sense of
+    fn parse(mountinfo: &str) -> Self {
+        let mounts = mountinfo
+            .lines()
+            .filter_map(|line| {
+                let (mount, fs) = line.split_once(" - ")?;
+                let mut fields = mount.split(' ').skip(2);
+                let (major, minor) = fields.next()?.split_once(':')?;
+                let mount_point = unescape(fields.nth(1)?);
+                Some(Mount {
+                    device: (major.parse().ok()?, minor.parse().ok()?),
+                    mount_point: PathBuf::from(mount_point),
+                    fs_type: fs.split(' ').next()?.to_string(),
+                })
+            })
+            .collect();
+        Self { mounts }
+    }
+
+    /// the type of the file system the entry is on. Its device tells the file system, unless the
+    /// file system reports another device than the one it is mounted from, as btrfs does. The
+    /// last file system mounted on a directory holding the entry tells it then
+    fn fs_type(&self, entry: &Entry) -> Option<&str> {
+        let dev = entry.metadata()?.dev();
+        let device = (libc::major(dev) as u32, libc::minor(dev) as u32);
+        if let Some(mount) = self.mounts.iter().rev().find(|m| m.device == device) {
+            return Some(&mount.fs_type);
+        }
+        self.mounted_on(&real_path(entry)?)
+    }
+
+    /// the type of the last file system mounted on the deepest directory holding the path
+    fn mounted_on(&self, path: &Path) -> Option<&str> {
+        // of the mounts equally deep, the last one found is kept
+        self.mounts
+            .iter()
+            .filter(|m| path.starts_with(&m.mount_point))
+            .max_by_key(|m| m.mount_point.components().count())
+            .map(|m| m.fs_type.as_str())
+    }
+}
+
+/// the absolute path of the entry with no symbolic link in it, but the entry itself when it is
+/// one, since its own file system is the one tested
+fn real_path(entry: &Entry) -> Option<PathBuf> {
+    let path = entry.path();
+    if !entry.file_type().is_symlink() {
+        return fs::canonicalize(path).ok();
+    }
+    let parent = match path.parent() {
+        Some(parent) if parent != Path::new("") => parent,
+        _ => Path::new("."),
+    };
+    Some(fs::canonicalize(parent).ok()?.join(entry.file_name()))
+}
+
+/// undo the octal escapes of the spaces, tabs, newlines and backslashes in mountinfo
+fn unescape(field: &str) -> String {
+    let mut unescaped = String::with_capacity(field.len());
+    let mut rest = field;
+    while let Some(i) = rest.find('\\') {
+        unescaped.push_str(&rest[..i]);
+        let code = rest
+            .get(i + 1..i + 4)
+            .and_then(|code| u8::from_str_radix(code, 8).ok());
+        match code {
+            Some(code) => {
+                unescaped.push(char::from(code));
+                rest = &rest[i + 4..];
+            }
+            None => {
+                unescaped.push('\\');
+                rest = &rest[i + 1..];
+            }
+        }
+    }
+    unescaped.push_str(rest);
+    unescaped
+}
+
+/// `-fstype TYPE`: the entry is on a file system of the type
+#[derive(Debug, Clone)]
+pub struct FsType {
+    fs_type: String,
+    mounts: Arc<Mounts>,
+}
+
+impl FsType {
+    pub fn new(fs_type: &str, mounts: Arc<Mounts>) -> Self {
+        Self {
+            fs_type: fs_type.to_string(),
+            mounts,
+        }
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        self.mounts.fs_type(entry) == Some(self.fs_type.as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_mountinfo() {
+        let mounts = Mounts::parse(
+            "22 1 259:2 / / rw,relatime shared:1 - ext4 /dev/nvme0n1p2 rw\n\
+             23 22 0:21 / /proc rw,nosuid - proc proc rw\n\
+             24 22 0:45 / /mnt/my\\040disk rw master:3 shared:7 - vfat /dev/sdb1 rw\n\
+             not a mount\n",
+        );
+        assert_eq!(
+            mounts.mounts,
+            [
+                Mount {
+                    device: (259, 2),
+                    mount_point: PathBuf::from("/"),
+                    fs_type: "ext4".to_string()
+                },
+                Mount {
+                    device: (0, 21),
+                    mount_point: PathBuf::from("/proc"),
+                    fs_type: "proc".to_string()
+                },
+                Mount {
+                    device: (0, 45),
+                    mount_point: PathBuf::from("/mnt/my disk"),
+                    fs_type: "vfat".to_string()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn find_last_mount_on_deepest_directory() {
+        let mounts = Mounts::parse(
+            "22 1 259:2 / / rw - ext4 /dev/nvme0n1p2 rw\n\
+             24 22 8:17 / /mnt rw - vfat /dev/sdb1 rw\n\
+             25 24 0:50 / /mnt rw - tmpfs tmpfs rw\n",
+        );
+        assert_eq!(mounts.mounted_on(Path::new("/mnt/a/b")), Some("tmpfs"));
+        assert_eq!(mounts.mounted_on(Path::new("/mnt")), Some("tmpfs"));
+        assert_eq!(mounts.mounted_on(Path::new("/mntx")), Some("ext4"));
+        assert_eq!(Mounts::default().mounted_on(Path::new("/")), None);
+    }
+
+    #[test]
+    fn unescape_mount_points() {
+        assert_eq!(unescape("/a\\040b\\011c\\134d"), "/a b\tc\\d");
+        assert_eq!(unescape("/trailing\\"), "/trailing\\");
+        assert_eq!(unescape("/no\\9escape"), "/no\\9escape");
+    }
+}
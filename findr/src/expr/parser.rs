@@ -0,0 +1,875 @@
+use super::action::{Exec, Output, Print, Sink};
+use super::content::Contains;
+use super::format::Format;
+use super::metadata::{Numeric, Size, Time, TimeField};
+#[cfg(target_os = "linux")]
+use super::mounts::{FsType, Mounts};
+use super::pattern::{self, RegexType};
+use super::Expr;
+use crate::cli::FileType;
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+const DAY: i64 = 24 * 60 * 60;
+const MINUTE: i64 = 60;
+
+/// the primaries the parser knows, with the number of arguments each takes
+const PRIMARIES: &[(&str, usize)] = &[
+    ("-true", 0),
+    ("-false", 0),
+    ("-name", 1),
+    ("-iname", 1),
+    ("-path", 1),
+    ("-ipath", 1),
+    ("-regex", 1),
+    ("-iregex", 1),
+    ("-regextype", 1),
+    ("-contains", 1),
+    ("-icontains", 1),
+    ("-type", 1),
+    ("-xtype", 1),
+    ("-size", 1),
+    ("-atime", 1),
+    ("-amin", 1),
+    ("-mtime", 1),
+    ("-mmin", 1),
+    ("-ctime", 1),
+    ("-cmin", 1),
+    ("-newer", 1),
+    ("-perm", 1),
+    ("-user", 1),
+    ("-group", 1),
+    ("-uid", 1),
+    ("-gid", 1),
+    ("-nouser", 0),
+    ("-nogroup", 0),
+    ("-empty", 0),
+    ("-prune", 0),
+    ("-depth", 0),
+    ("-xdev", 0),
+    ("-mount", 0),
+    ("-fstype", 1),
+    ("-print", 0),
+    ("-print0", 0),
+    ("-printf", 1),
+    ("-fprint", 1),
+    ("-fprint0", 1),
+    ("-fprintf", 2),
+    ("-delete", 0),
+];
+/// the primaries running a command, whose arguments run up to a `;`, or a `{}` followed by `+`
+const EXEC_PRIMARIES: &[&str] = &["-exec", "-execdir", "-ok"];
+const OPERATORS: &[&str] = &["(", ")", "!", "-not", "-a", "-and", "-o", "-or"];
+
+/// An expression that could not be parsed, pointing at the token at fault
+#[derive(Debug, PartialEq)]
+pub struct ParseError {
+    message: String,
+    /// index of the offending token, or the number of tokens when the expression ends too early
+    position: usize,
+}
+
+impl ParseError {
+    fn new(message: impl Into<String>, position: usize) -> Self {
+        Self {
+            message: message.into(),
+            position,
+        }
+    }
+
+    /// the error message followed by the expression with the offending token underlined
+    pub fn render(&self, tokens: &[String]) -> String {
+        let offset = tokens
+            .iter()
+            .take(self.position)
+            .map(|token| token.chars().count() + 1)
+            .sum::<usize>();
+        let width = tokens
+            .get(self.position)
+            .map_or(1, |token| token.chars().count().max(1));
+        format!(
+            "{}\n\n  {}\n  {}{}",
+            self.message,
+            tokens.join(" "),
+            " ".repeat(offset),
+            "^".repeat(width)
+        )
+    }
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.message)
+    }
+}
+
+/// Separate the expression from the command line arguments clap understands. Operators and
+/// single dash words such as `-name` start an expression token, and the arguments of a
+/// primary are taken along with it whatever they look like
+pub fn split_args(args: impl IntoIterator<Item = OsString>) -> (Vec<OsString>, Vec<String>) {
+    let mut args = args.into_iter();
+    let mut clap_args = args.next().into_iter().collect::<Vec<_>>();
+    let mut tokens = vec![];
+
+    while let Some(arg) = args.next() {
+        match arg.to_str() {
+            Some("--") => {
+                clap_args.push(arg);
+                clap_args.extend(args.by_ref());
+            }
+            Some(token) if EXEC_PRIMARIES.contains(&token) => {
+                let batches = token != "-ok";
+                tokens.push(token.to_string());
+                for arg in args.by_ref() {
+                    let arg = arg.to_string_lossy().into_owned();
+                    let ends = arg == ";"
+                        || (batches && arg == "+" && tokens.last().is_some_and(|t| t == "{}"));
+                    tokens.push(arg);
+                    if ends {
+                        break;
+                    }
+                }
+            }
+            Some(token) if is_expression_token(token) => {
+                let arity = arity(token);
+                tokens.push(token.to_string());
+                tokens.extend(
+                    args.by_ref()
+                        .take(arity)
+                        .map(|arg| arg.to_string_lossy().into_owned()),
+                );
+            }
+            _ => clap_args.push(arg),
+        }
+    }
+    (clap_args, tokens)
+}
+
+fn is_expression_token(token: &str) -> bool {
+    OPERATORS.contains(&token)
+        || (token.len() > 2
+            && token.starts_with('-')
+            && token[1..].starts_with(|c: char| c.is_ascii_alphabetic())
+            // a short option with its value, such as `-j4`
+            && !token[2..].starts_with(|c: char| c.is_ascii_digit()))
+}
+
+fn arity(token: &str) -> usize {
+    PRIMARIES
+        .iter()
+        .find(|(name, _)| *name == token)
+        .map_or(0, |(_, arity)| *arity)
+}
+
+/// Parse a find-style expression. `!` binds tighter than `-and`, which binds tighter than `-or`,
+/// and two expressions next to each other are joined with `-and`
+pub fn parse(tokens: &[String]) -> Result<Option<Expr>, ParseError> {
+    if tokens.is_empty() {
+        return Ok(None);
+    }
+    let mut parser = Parser {
+        tokens,
+        position: 0,
+        regex_type: RegexType::default(),
+        start: SystemTime::now(),
+        files: HashMap::new(),
+        #[cfg(target_os = "linux")]
+        mounts: None,
+    };
+    let expr = parser.parse_or()?;
+    match parser.peek() {
+        None => Ok(Some(expr)),
+        Some(")") => Err(ParseError::new("unmatched ')'", parser.position)),
+        Some(token) => Err(ParseError::new(
+            format!("unexpected '{}'", token),
+            parser.position,
+        )),
+    }
+}
+
+struct Parser<'a> {
+    tokens: &'a [String],
+    position: usize,
+    /// the syntax of the following `-regex` patterns, as set by the last `-regextype`
+    regex_type: RegexType,
+    /// the time the ages of `-mtime` and the like are measured from
+    start: SystemTime,
+    /// the files of `-fprint` and the like, opened once however many primaries name them
+    files: HashMap<&'a str, Sink>,
+    /// the file systems `-fstype` looks up, read when first needed
+    #[cfg(target_os = "linux")]
+    mounts: Option<Arc<Mounts>>,
+}
+
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next_if(&mut self, expected: &[&str]) -> bool {
+        let found = self.peek().is_some_and(|token| expected.contains(&token));
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    /// fail unless an operand follows the operator just read
+    fn expect_operand(&self) -> Result<(), ParseError> {
+        match self.peek() {
+            None | Some(")" | "-a" | "-and" | "-o" | "-or") => {
+                let operator = self.position - 1;
+                Err(ParseError::new(
+                    format!("expected an expression after '{}'", self.tokens[operator]),
+                    operator,
+                ))
+            }
+            Some(_) => Ok(()),
+        }
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_and()?;
+        while self.next_if(&["-o", "-or"]) {
+            self.expect_operand()?;
+            expr = Expr::Or(Box::new(expr), Box::new(self.parse_and()?));
+        }
+        Ok(expr)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_not()?;
+        loop {
+            if self.next_if(&["-a", "-and"]) {
+                self.expect_operand()?;
+            } else if matches!(self.peek(), None | Some(")" | "-o" | "-or")) {
+                return Ok(expr);
+            }
+            expr = Expr::And(Box::new(expr), Box::new(self.parse_not()?));
+        }
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        if self.next_if(&["!", "-not"]) {
+            self.expect_operand()?;
+            return Ok(Expr::Not(Box::new(self.parse_not()?)));
+        }
+        self.parse_primary()
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let position = self.position;
+        let tokens = self.tokens;
+        let Some(token) = tokens.get(position).map(String::as_str) else {
+            return Err(ParseError::new("expected an expression", position));
+        };
+        self.position += 1;
+        match token {
+            "(" => {
+                self.expect_operand()?;
+                let expr = self.parse_or()?;
+                if !self.next_if(&[")"]) {
+                    return Err(ParseError::new("unmatched '('", position));
+                }
+                Ok(expr)
+            }
+            "-a" | "-and" | "-o" | "-or" => Err(ParseError::new(
+                format!("expected an expression before '{}'", token),
+                position,
+            )),
+            "-size" => {
+                let size = self.argument(position)?;
+                Size::parse(size).map(Expr::Size).ok_or_else(|| {
+                    ParseError::new(
+                        format!(
+                            "invalid size '{}' for '-size', expected a number optionally followed by one of b, c, w, k, M, G",
+                            size
+                        ),
+                        position + 1,
+                    )
+                })
+            }
+            "-atime" | "-amin" | "-mtime" | "-mmin" | "-ctime" | "-cmin" => {
+                let field = match &token[1..2] {
+                    "a" => TimeField::Access,
+                    "m" => TimeField::Modify,
+                    #[cfg(unix)]
+                    _ => TimeField::Change,
+                    #[cfg(not(unix))]
+                    _ => return Err(unsupported(token, position)),
+                };
+                let unit = if token.ends_with("min") { MINUTE } else { DAY };
+                let numeric = self.numeric(position)?;
+                Ok(Expr::Time(Time::new(field, numeric, unit, self.start)))
+            }
+            "-newer" => {
+                let file = self.argument(position)?;
+                fs::metadata(file)
+                    .and_then(|metadata| metadata.modified())
+                    .map(Expr::Newer)
+                    .map_err(|e| {
+                        ParseError::new(
+                            format!("cannot read '{}' for '-newer': {}", file, e),
+                            position + 1,
+                        )
+                    })
+            }
+            #[cfg(unix)]
+            "-perm" => {
+                let mode = self.argument(position)?;
+                super::metadata::Perm::parse(mode)
+                    .map(Expr::Perm)
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            format!("invalid mode '{}' for '-perm'", mode),
+                            position + 1,
+                        )
+                    })
+            }
+            #[cfg(unix)]
+            "-user" => {
+                let name = self.argument(position)?;
+                uzers::get_user_by_name(name)
+                    .map(|user| user.uid())
+                    .or_else(|| name.parse().ok())
+                    .map(|uid| Expr::Uid(Numeric::exactly(uid.into())))
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            format!("unknown user '{}' for '-user'", name),
+                            position + 1,
+                        )
+                    })
+            }
+            #[cfg(unix)]
+            "-group" => {
+                let name = self.argument(position)?;
+                uzers::get_group_by_name(name)
+                    .map(|group| group.gid())
+                    .or_else(|| name.parse().ok())
+                    .map(|gid| Expr::Gid(Numeric::exactly(gid.into())))
+                    .ok_or_else(|| {
+                        ParseError::new(
+                            format!("unknown group '{}' for '-group'", name),
+                            position + 1,
+                        )
+                    })
+            }
+            #[cfg(unix)]
+            "-uid" => self.numeric(position).map(Expr::Uid),
+            #[cfg(unix)]
+            "-gid" => self.numeric(position).map(Expr::Gid),
+            #[cfg(unix)]
+            "-nouser" => Ok(Expr::NoUser),
+            #[cfg(unix)]
+            "-nogroup" => Ok(Expr::NoGroup),
+            #[cfg(not(unix))]
+            "-perm" | "-user" | "-group" | "-uid" | "-gid" | "-nouser" | "-nogroup" => {
+                Err(unsupported(token, position))
+            }
+            "-empty" => Ok(Expr::Empty),
+            "-prune" => Ok(Expr::Prune),
+            "-depth" => Ok(Expr::Depth),
+            "-xdev" | "-mount" => Ok(Expr::XDev),
+            #[cfg(target_os = "linux")]
+            "-fstype" => {
+                let fs_type = self.argument(position)?;
+                let mounts = match &self.mounts {
+                    Some(mounts) => Arc::clone(mounts),
+                    None => {
+                        let mounts = Arc::new(Mounts::read().map_err(|e| {
+                            ParseError::new(
+                                format!(
+                                    "cannot read the mounted file systems for '-fstype': {}",
+                                    e
+                                ),
+                                position,
+                            )
+                        })?);
+                        self.mounts.insert(mounts).clone()
+                    }
+                };
+                Ok(Expr::FsType(FsType::new(fs_type, mounts)))
+            }
+            #[cfg(not(target_os = "linux"))]
+            "-fstype" => Err(unsupported(token, position)),
+            "-print" | "-print0" | "-printf" | "-fprint" | "-fprint0" | "-fprintf" => {
+                self.parse_print(position)
+            }
+            "-delete" => Ok(Expr::Delete),
+            "-exec" | "-execdir" | "-ok" => self.parse_exec(position),
+            "-true" => Ok(Expr::True),
+            "-false" => Ok(Expr::False),
+            "-name" | "-iname" | "-path" | "-ipath" => {
+                let pattern = self.argument(position)?;
+                let glob = pattern::glob(pattern, token.starts_with("-i")).map_err(|_| {
+                    ParseError::new(
+                        format!("invalid glob '{}' for '{}'", pattern, token),
+                        position + 1,
+                    )
+                })?;
+                Ok(match token {
+                    "-name" | "-iname" => Expr::Name(glob),
+                    _ => Expr::Path(glob),
+                })
+            }
+            "-regex" | "-iregex" => {
+                let pattern = self.argument(position)?;
+                pattern::regex(pattern, self.regex_type, token == "-iregex")
+                    .map(Expr::Regex)
+                    .map_err(|_| {
+                        ParseError::new(
+                            format!("invalid regular expression '{}' for '{}'", pattern, token),
+                            position + 1,
+                        )
+                    })
+            }
+            "-contains" | "-icontains" => {
+                let pattern = self.argument(position)?;
+                Contains::new(pattern, token == "-icontains")
+                    .map(Expr::Contains)
+                    .map_err(|_| {
+                        ParseError::new(
+                            format!("invalid regular expression '{}' for '{}'", pattern, token),
+                            position + 1,
+                        )
+                    })
+            }
+            "-regextype" => {
+                let name = self.argument(position)?;
+                self.regex_type = RegexType::from_name(name).ok_or_else(|| {
+                    ParseError::new(
+                        format!(
+                            "unknown regular expression type '{}', expected one of {}",
+                            name,
+                            RegexType::NAMES.join(", ")
+                        ),
+                        position + 1,
+                    )
+                })?;
+                Ok(Expr::True)
+            }
+            "-type" | "-xtype" => {
+                let list = self.argument(position)?;
+                let file_types = FileType::parse_list(list).ok_or_else(|| {
+                    ParseError::new(
+                        format!(
+                            "invalid type '{}' for '{}', expected one of {}",
+                            list,
+                            token,
+                            FileType::names()
+                        ),
+                        position + 1,
+                    )
+                })?;
+                let test = match token {
+                    "-type" => Expr::Type,
+                    _ => Expr::XType,
+                };
+                Ok(Expr::any(file_types.into_iter().map(test)).expect("has a file type"))
+            }
+            token if token.starts_with('-') => Err(ParseError::new(
+                format!("unknown primary '{}'", token),
+                position,
+            )),
+            token => Err(ParseError::new(
+                format!("expected an expression, found '{}'", token),
+                position,
+            )),
+        }
+    }
+
+    /// the command following the `-exec`, `-execdir` or `-ok` primary at `position`
+    fn parse_exec(&mut self, position: usize) -> Result<Expr, ParseError> {
+        let tokens = self.tokens;
+        let primary = tokens[position].as_str();
+        let batches = primary != "-ok";
+        let end = (self.position..tokens.len())
+            .find(|&i| {
+                tokens[i] == ";"
+                    || (batches && tokens[i] == "+" && i > self.position && tokens[i - 1] == "{}")
+            })
+            .ok_or_else(|| {
+                let terminators = if batches { "';' or '+'" } else { "';'" };
+                ParseError::new(
+                    format!("missing {} terminating '{}'", terminators, primary),
+                    position,
+                )
+            })?;
+        let batch = tokens[end] == "+";
+        // the `{}` before `+` stands for all the paths of the batch
+        let command = &tokens[self.position..if batch { end - 1 } else { end }];
+        if command.is_empty() {
+            return Err(ParseError::new(
+                format!("missing command for '{}'", primary),
+                position,
+            ));
+        }
+        if let Some(i) = command.iter().position(|arg| batch && arg.contains("{}")) {
+            return Err(ParseError::new(
+                "'{}' can only appear once with '+', right before it",
+                self.position + i,
+            ));
+        }
+        self.position = end + 1;
+        Ok(Expr::Exec(Exec::new(
+            command.to_vec(),
+            batch,
+            primary == "-execdir",
+            primary == "-ok",
+        )))
+    }
+
+    /// the output and the file, if any, of the printing primary at `position`
+    fn parse_print(&mut self, position: usize) -> Result<Expr, ParseError> {
+        let primary = self.tokens[position].as_str();
+        let sink = match primary.strip_prefix("-f") {
+            Some(_) => self.sink(position)?,
+            None => Sink::Stdout,
+        };
+        let output = if primary.ends_with('0') {
+            Output::Nul
+        } else if primary.ends_with('f') {
+            let format = self.argument(position)?;
+            Output::Format(
+                Format::parse(format)
+                    .map_err(|message| ParseError::new(message, self.position - 1))?,
+            )
+        } else {
+            Output::Line
+        };
+        Ok(Expr::Print(Print::new(output, sink)))
+    }
+
+    /// the file named by the argument of the primary at `position`, created when first named
+    fn sink(&mut self, position: usize) -> Result<Sink, ParseError> {
+        let path = self.argument(position)?;
+        if let Some(sink) = self.files.get(path) {
+            return Ok(sink.clone());
+        }
+        let file = File::create(path).map_err(|e| {
+            ParseError::new(
+                format!(
+                    "cannot create '{}' for '{}': {}",
+                    path, self.tokens[position], e
+                ),
+                position + 1,
+            )
+        })?;
+        let sink = Sink::File(
+            PathBuf::from(path),
+            Arc::new(Mutex::new(BufWriter::new(file))),
+        );
+        self.files.insert(path, sink.clone());
+        Ok(sink)
+    }
+
+    /// the `[+-]N` argument following the primary at `position`
+    fn numeric(&mut self, position: usize) -> Result<Numeric, ParseError> {
+        let number = self.argument(position)?;
+        Numeric::parse(number).ok_or_else(|| {
+            ParseError::new(
+                format!(
+                    "invalid number '{}' for '{}', expected N, +N or -N",
+                    number, self.tokens[position]
+                ),
+                position + 1,
+            )
+        })
+    }
+
+    /// the argument following the primary at `position`
+    fn argument(&mut self, position: usize) -> Result<&'a str, ParseError> {
+        let tokens = self.tokens;
+        match tokens.get(self.position) {
+            Some(argument) => {
+                self.position += 1;
+                Ok(argument)
+            }
+            None => Err(ParseError::new(
+                format!("missing argument to '{}'", tokens[position]),
+                position,
+            )),
+        }
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn unsupported(primary: &str, position: usize) -> ParseError {
+    ParseError::new(
+        format!("'{}' is not supported on this platform", primary),
+        position,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(expr: &str) -> Vec<String> {
+        expr.split_whitespace().map(String::from).collect()
+    }
+
+    fn parse_debug(expr: &str) -> String {
+        format!("{:?}", parse(&tokens(expr)).expect("parses expression"))
+    }
+
+    fn parse_error(expr: &str) -> ParseError {
+        parse(&tokens(expr)).expect_err("fails to parse expression")
+    }
+
+    #[test]
+    fn parse_operator_precedence() {
+        assert_eq!(
+            parse_debug("-name a -o -name b -type d"),
+            parse_debug("-name a -o ( -name b -a -type d )"),
+            "and binds tighter than or"
+        );
+        assert_eq!(
+            parse_debug("! -name a -and -type f"),
+            parse_debug("( -not -name a ) -a -type f"),
+            "not binds tighter than and"
+        );
+        assert_eq!(
+            parse_debug("( -type f -or -type d ) -a ! -type l"),
+            "Some(And(Or(Type(File), Type(Dir)), Not(Type(Link))))",
+            "parentheses group expressions"
+        );
+        assert_eq!(
+            parse_debug(""),
+            "None",
+            "an empty expression matches everything"
+        );
+    }
+
+    #[test]
+    fn parse_errors_point_at_token() {
+        assert_eq!(
+            parse_error("-name a -nmae b"),
+            ParseError::new("unknown primary '-nmae'", 2)
+        );
+        assert_eq!(
+            parse_error("( -name a"),
+            ParseError::new("unmatched '('", 0)
+        );
+        assert_eq!(
+            parse_error("-name a )"),
+            ParseError::new("unmatched ')'", 2)
+        );
+        assert_eq!(
+            parse_error("-name a -o"),
+            ParseError::new("expected an expression after '-o'", 2)
+        );
+        assert_eq!(
+            parse_error("-a -name a"),
+            ParseError::new("expected an expression before '-a'", 0)
+        );
+        assert_eq!(
+            parse_error("( )"),
+            ParseError::new("expected an expression after '('", 0)
+        );
+        assert_eq!(
+            parse_error("-type x"),
+            ParseError::new(
+                format!(
+                    "invalid type 'x' for '-type', expected one of {}",
+                    FileType::names()
+                ),
+                1
+            )
+        );
+        assert_eq!(
+            parse_error("-xtype x"),
+            ParseError::new(
+                format!(
+                    "invalid type 'x' for '-xtype', expected one of {}",
+                    FileType::names()
+                ),
+                1
+            )
+        );
+        assert_eq!(
+            parse_error("-type d -name"),
+            ParseError::new("missing argument to '-name'", 2)
+        );
+    }
+
+    #[test]
+    fn parse_type_lists() {
+        assert_eq!(
+            parse_debug("-type f,l"),
+            parse_debug("( -type f -o -type l )")
+        );
+        assert!(parse_debug("-xtype d").contains("XType(Dir)"));
+        assert_eq!(
+            parse_error("-type f,"),
+            ParseError::new(
+                format!(
+                    "invalid type 'f,' for '-type', expected one of {}",
+                    FileType::names()
+                ),
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn parse_patterns() {
+        assert!(
+            parse_debug("-regex a|b").contains(r#"Regex("^(?:a|b)$")"#),
+            "anchors regular expressions to the whole path"
+        );
+        assert!(
+            parse_debug(r"-regextype posix-basic -regex \(a\|b\)+")
+                .contains(r#"Regex("^(?:(a|b)\\+)$")"#),
+            "applies the regular expression type to the following patterns"
+        );
+        assert_eq!(
+            parse_error("-iname a[b"),
+            ParseError::new("invalid glob 'a[b' for '-iname'", 1)
+        );
+        assert_eq!(
+            parse_error("-regex a( -o -name b"),
+            ParseError::new("invalid regular expression 'a(' for '-regex'", 1)
+        );
+        assert_eq!(
+            parse_error("-name a -icontains *"),
+            ParseError::new("invalid regular expression '*' for '-icontains'", 3)
+        );
+        assert_eq!(
+            parse_error("-regextype sed -regex a"),
+            ParseError::new(
+                "unknown regular expression type 'sed', expected one of rust, posix-extended, egrep, posix-basic",
+                1
+            )
+        );
+    }
+
+    #[test]
+    fn parse_exec_commands() {
+        assert!(parse_debug("-exec echo {} ; -print").contains(", Print(Print { output: Line"));
+        assert!(parse_debug("-exec echo {} + -o -print").contains("batch: true"));
+        assert!(parse_debug("-ok echo {} +{} ;").contains(r#"command: ["echo", "{}", "+{}"]"#));
+        assert_eq!(
+            parse_error("-exec echo {}"),
+            ParseError::new("missing ';' or '+' terminating '-exec'", 0)
+        );
+        assert_eq!(
+            parse_error("-print -ok echo {} +"),
+            ParseError::new("missing ';' terminating '-ok'", 1)
+        );
+        assert_eq!(
+            parse_error("-execdir ;"),
+            ParseError::new("missing command for '-execdir'", 0)
+        );
+        assert_eq!(
+            parse_error("-exec cp {} dir {} +"),
+            ParseError::new("'{}' can only appear once with '+', right before it", 2)
+        );
+    }
+
+    #[test]
+    fn parse_print_primaries() {
+        assert!(parse_debug("-print0").contains("Print(Print { output: Nul, sink: Stdout })"));
+        assert!(parse_debug("-printf %p").contains("output: Format("));
+        assert_eq!(
+            parse_error("-printf %q -print"),
+            ParseError::new("unknown directive '%q' in '-printf' format", 1)
+        );
+        assert_eq!(
+            parse_error("-fprintf"),
+            ParseError::new("missing argument to '-fprintf'", 0)
+        );
+        assert!(parse_error("-fprint no-such-dir/out")
+            .message
+            .starts_with("cannot create 'no-such-dir/out' for '-fprint': "));
+    }
+
+    #[test]
+    fn parse_metadata_tests() {
+        assert!(parse_debug("-size +1k").contains(
+            "Size(Size { numeric: Numeric { ordering: Greater, value: 1 }, unit: 1024 })"
+        ));
+        assert!(parse_debug("-mmin -5")
+            .contains("field: Modify, numeric: Numeric { ordering: Less, value: 5 }, unit: 60"));
+        assert!(parse_debug("-newer Cargo.toml").starts_with("Some(Newer("));
+        assert_eq!(
+            parse_error("-size 1x"),
+            ParseError::new(
+                "invalid size '1x' for '-size', expected a number optionally followed by one of b, c, w, k, M, G",
+                1
+            )
+        );
+        assert_eq!(
+            parse_error("-atime two"),
+            ParseError::new("invalid number 'two' for '-atime', expected N, +N or -N", 1)
+        );
+        assert!(parse_error("-newer blargh")
+            .message
+            .starts_with("cannot read 'blargh' for '-newer': "));
+    }
+
+    #[test]
+    #[cfg(unix)]
+    fn parse_owner_tests() {
+        assert!(parse_debug("-user root").contains("Uid(Numeric { ordering: Equal, value: 0 })"));
+        assert!(
+            parse_debug("-user 12345").contains("Uid(Numeric { ordering: Equal, value: 12345 })")
+        );
+        assert!(parse_debug("-gid +10").contains("Gid(Numeric { ordering: Greater, value: 10 })"));
+        assert_eq!(
+            parse_error("-user no-such-user"),
+            ParseError::new("unknown user 'no-such-user' for '-user'", 1)
+        );
+        assert_eq!(
+            parse_error("-perm u+q"),
+            ParseError::new("invalid mode 'u+q' for '-perm'", 1)
+        );
+    }
+
+    #[test]
+    fn render_error_under_token() {
+        let expr = tokens("-name a -nmae b");
+        assert_eq!(
+            parse(&expr).unwrap_err().render(&expr),
+            "unknown primary '-nmae'\n\n  -name a -nmae b\n          ^^^^^"
+        );
+        let expr = tokens("-name a -o");
+        assert_eq!(
+            ParseError::new("expected an expression", 3).render(&expr),
+            "expected an expression\n\n  -name a -o\n             ^"
+        );
+    }
+
+    #[test]
+    fn split_expression_from_options() {
+        let args = [
+            "findr",
+            "dir",
+            "--maxdepth",
+            "1",
+            "(",
+            "-name",
+            "-o",
+            ")",
+            "--type",
+            "f",
+        ]
+        .map(OsString::from);
+        let (clap_args, tokens) = split_args(args);
+        assert_eq!(
+            clap_args,
+            ["findr", "dir", "--maxdepth", "1", "--type", "f"]
+        );
+        assert_eq!(
+            tokens,
+            ["(", "-name", "-o", ")"],
+            "takes the argument of a primary along"
+        );
+    }
+}
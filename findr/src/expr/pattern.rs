@@ -0,0 +1,123 @@
+use globset::{GlobBuilder, GlobMatcher};
+use regex::{Regex, RegexBuilder};
+
+/// A shell glob matched as find does: `*` and `?` also match `/` and a leading `.`
+pub fn glob(pattern: &str, case_insensitive: bool) -> Result<GlobMatcher, globset::Error> {
+    GlobBuilder::new(pattern)
+        .case_insensitive(case_insensitive)
+        .backslash_escape(true)
+        .build()
+        .map(|glob| glob.compile_matcher())
+}
+
+/// The syntax of the patterns given to `-regex` and `-iregex`
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RegexType {
+    /// the syntax of the regex crate, close to POSIX extended regular expressions
+    #[default]
+    Rust,
+    /// POSIX basic regular expressions, where `\(`, `\)`, `\{`, `\}`, `\|`, `\+` and `\?` are
+    /// operators and the unescaped characters match themselves
+    PosixBasic,
+}
+
+impl RegexType {
+    pub const NAMES: &'static [&'static str] = &["rust", "posix-extended", "egrep", "posix-basic"];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "rust" | "posix-extended" | "egrep" => Some(Self::Rust),
+            "posix-basic" => Some(Self::PosixBasic),
+            _ => None,
+        }
+    }
+}
+
+/// A regular expression which, as in find, has to match the whole path
+pub fn regex(
+    pattern: &str,
+    regex_type: RegexType,
+    case_insensitive: bool,
+) -> Result<Regex, regex::Error> {
+    let pattern = match regex_type {
+        RegexType::Rust => pattern.to_string(),
+        RegexType::PosixBasic => from_posix_basic(pattern),
+    };
+    RegexBuilder::new(&format!("^(?:{})$", pattern))
+        .case_insensitive(case_insensitive)
+        .build()
+}
+
+/// translate a POSIX basic regular expression into the regex crate syntax, leaving bracket
+/// expressions untouched
+fn from_posix_basic(pattern: &str) -> String {
+    let mut translated = String::with_capacity(pattern.len());
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(c @ ('(' | ')' | '{' | '}' | '|' | '+' | '?')) => translated.push(c),
+                Some(c) => {
+                    translated.push('\\');
+                    translated.push(c);
+                }
+                None => translated.push_str("\\\\"),
+            },
+            '(' | ')' | '{' | '}' | '|' | '+' | '?' => {
+                translated.push('\\');
+                translated.push(c);
+            }
+            '[' => {
+                translated.push(c);
+                // a `]` right after the opening bracket or its negation is part of the set
+                if let Some(c) = chars.next_if_eq(&'^') {
+                    translated.push(c);
+                }
+                if let Some(c) = chars.next_if_eq(&']') {
+                    translated.push('\\');
+                    translated.push(c);
+                }
+                for c in chars.by_ref() {
+                    translated.push(c);
+                    if c == ']' {
+                        break;
+                    }
+                }
+            }
+            c => translated.push(c),
+        }
+    }
+    translated
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn match_globs_like_find() -> anyhow::Result<()> {
+        assert!(glob("*.csv", false)?.is_match("b.csv"));
+        assert!(!glob("*.csv", false)?.is_match("b.CSV"));
+        assert!(glob("*.csv", true)?.is_match("b.CSV"));
+        assert!(glob("*/b/*", false)?.is_match("tests/inputs/a/b/c/c.mp3"));
+        assert!(glob("\\*", false)?.is_match("*"));
+        assert!(glob("a[b", false).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn match_whole_path_with_regex() -> anyhow::Result<()> {
+        assert!(regex(".*[.]csv", RegexType::Rust, false)?.is_match("a/b.csv"));
+        assert!(!regex("[.]csv", RegexType::Rust, false)?.is_match("a/b.csv"));
+        assert!(regex(".*B.CSV", RegexType::Rust, true)?.is_match("a/b.csv"));
+        Ok(())
+    }
+
+    #[test]
+    fn translate_posix_basic_regex() {
+        assert_eq!(from_posix_basic("\\(a\\|b\\)\\{2\\}"), "(a|b){2}");
+        assert_eq!(from_posix_basic("(a|b)+?"), "\\(a\\|b\\)\\+\\?");
+        assert_eq!(from_posix_basic("[]()]x\\.y"), "[\\]()]x\\.y");
+        assert_eq!(from_posix_basic("[^]a]"), "[^\\]a]");
+    }
+}
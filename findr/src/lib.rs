@@ -1,71 +1,172 @@
-use clap::Parser;
-use cli::FindrArgs;
+use cli::{FileType, FindrArgs, SortKey, Symlinks};
+use duplicates::Files;
+use entry::Entry;
 use error_utils::{ProgramError, ProgramErrorParams};
+use expr::Expr;
+use results::{Limit, Rank, Top};
+use std::borrow::Cow;
 use std::error;
-use walkdir::{DirEntry, WalkDir};
+use std::io;
+use std::path::Path;
+use usage::Usage;
+use walkdir::WalkDir;
 
 mod cli;
+mod duplicates;
+mod entry;
+mod expr;
+mod output;
+mod parallel;
+mod results;
+mod usage;
 
 type ProgramResult<'a> = anyhow::Result<(), ProgramError<'a>>;
 
 pub fn run<'b>() -> ProgramResult<'b> {
-    let args = FindrArgs::parse();
+    let args = FindrArgs::parse_with_expression();
     find_matches(&args)?;
     Ok(())
 }
 
-fn find_matches<'a>(args: &'a FindrArgs) -> ProgramResult<'static> {
-    args.paths.iter().for_each(|p| process_path(p, args));
-    Ok(())
-}
-
-fn process_path(p: &str, args: &FindrArgs) {
-    // examples of using defining closures
-    let type_filter = |entry: &DirEntry| -> bool {
-        args.file_types.is_empty()
-            || args
-                .file_types
-                .iter()
-                .any(|f_type| f_type.is_type(&entry.file_type()))
+fn find_matches(args: &FindrArgs) -> ProgramResult<'static> {
+    if args.is_json() {
+        output::report_as_json();
+    }
+    let files = args.duplicates.then(|| Files::new(args.min_size));
+    let top = match (args.newest, args.largest) {
+        (Some(n), _) => Some(Top::new(Rank::Newest, n)),
+        (_, Some(n)) => Some(Top::new(Rank::Largest, n)),
+        _ => None,
     };
-    let name_filter = |entry: &DirEntry| -> bool {
-        args.names.is_empty()
-            || args
-                .names
-                .iter()
-                .any(|name| name.is_match(&entry.path().to_string_lossy()))
+    // gathering files or ranking entries replaces printing them
+    let gather = files
+        .clone()
+        .map(Expr::Gather)
+        .or_else(|| top.clone().map(Expr::Keep));
+    let usage = args
+        .du
+        .then(|| Usage::new(args.is_json(), args.min_depth, args.max_depth));
+    let mut expr = match (gather, &usage) {
+        // every directory has its total printed, which only the regular files matching add to
+        (_, Some(usage)) => {
+            let dirs = [Expr::Type(FileType::Dir), Expr::Usage(usage.clone())];
+            let files = args
+                .filter()
+                .into_iter()
+                .chain([Expr::Usage(usage.clone())]);
+            Expr::any([Expr::all(dirs), Expr::all(files)].into_iter().flatten())
+                .expect("has an expression")
+        }
+        (Some(gather), None) => {
+            Expr::all(args.filter().into_iter().chain([gather])).expect("has an expression")
+        }
+        (None, None) => Expr::with_default_action(args.filter(), args.default_print()),
     };
+    let limit = Limit::new(args.limit);
+    for p in &args.paths {
+        if limit.is_reached() {
+            break;
+        }
+        process_path(p, args, &mut expr, &limit);
+    }
+    expr.finish();
+    if let Some(files) = files {
+        duplicates::print(&files.groups(), args.json || args.is_json());
+    }
+    if let Some(top) = top {
+        let mut print = args.default_print();
+        for entry in top.entries(args.reverse) {
+            print.run(&entry);
+        }
+        print.finish();
+    }
+    Ok(())
+}
 
-    let out = new_walker(p, args)
-        .into_iter()
-        .filter_map(|res| match res {
+fn process_path(p: &str, args: &FindrArgs, expr: &mut Expr, limit: &Limit) {
+    // walkdir deletes directories once their contents are gone without gathering the entries
+    // first, while only the walker of the ignore crate knows about ignore files. Sorting by
+    // anything but the path gathers the entries too
+    let gathers = args.sort.is_some_and(|key| key != SortKey::Path);
+    if args.respect_ignore || ((args.jobs.get() > 1 || gathers) && !expr.deletes()) {
+        return parallel::process_path(p, args, expr, limit);
+    }
+    let symlinks = args.symlinks();
+    // a directory only comes once its contents are done when walking them first
+    let prunes = !expr.contents_first();
+    let mut walker = new_walker(p, args, expr).into_iter();
+    while let Some(res) = walker.next() {
+        let entry = match res {
+            Ok(entry) => {
+                let follow = symlinks.follows(entry.depth());
+                Entry::from(entry).follow_links(follow)
+            }
             Err(e) => {
-                eprintln!("{}", translate_error(Box::new(e), p));
-                None
+                let broken_link = e
+                    .io_error()
+                    .filter(|io_err| io_err.kind() == io::ErrorKind::NotFound)
+                    .and(e.path())
+                    .and_then(|path| Entry::link(path, e.depth()));
+                match broken_link {
+                    Some(entry) => entry,
+                    None => {
+                        let path = e.path().map(Path::to_path_buf);
+                        report_walk_error(Box::new(e), path.as_deref(), p);
+                        continue;
+                    }
+                }
             }
-            Ok(entry) => Some(entry),
-        })
-        .filter(type_filter)
-        .filter(name_filter)
-        .map(|entry| entry.path().display().to_string())
-        .collect::<Vec<_>>()
-        .join("\n");
-
-    println!("{out}");
+        };
+        if !limit.eval(expr, &entry) {
+            break;
+        }
+        if prunes && entry.is_pruned() {
+            walker.skip_current_dir();
+        }
+    }
 }
 
-fn new_walker(p: &str, args: &FindrArgs) -> WalkDir {
-    let mut walker = WalkDir::new(p);
-    if let Some(depth) = args.max_depth {
+fn new_walker(p: &str, args: &FindrArgs, expr: &Expr) -> WalkDir {
+    // directories can only be deleted, or added up, once their contents are
+    let symlinks = args.symlinks();
+    let mut walker = WalkDir::new(p)
+        .contents_first(expr.contents_first())
+        .follow_links(symlinks == Symlinks::All)
+        .follow_root_links(symlinks != Symlinks::None)
+        .same_file_system(expr.stays_on_file_system());
+    let (min_depth, max_depth) = args.walk_depths();
+    if let Some(depth) = max_depth {
         walker = walker.max_depth(depth);
     }
-    if let Some(depth) = args.min_depth {
+    if let Some(depth) = min_depth {
         walker = walker.min_depth(depth);
     }
+    if args.sort.is_some() {
+        let reverse = args.reverse;
+        walker = walker.sort_by(move |a, b| match reverse {
+            true => b.file_name().cmp(a.file_name()),
+            false => a.file_name().cmp(b.file_name()),
+        });
+    }
     walker
 }
 
-fn translate_error(e: Box<dyn error::Error>, pathname: &str) -> ProgramError {
+/// report an error of a walker, naming the path it happened at, or else the starting path
+pub(crate) fn report_walk_error(e: Box<dyn error::Error>, path: Option<&Path>, root: &str) {
+    let pathname = path.map_or(Cow::from(root), Path::to_string_lossy);
+    output::report(translate_error(e, &pathname));
+}
+
+/// the error of a walker finding a directory it is already within, through a symbolic link
+pub(crate) fn translate_loop<'a>(pathname: &'a str, ancestor: &Path) -> ProgramError<'a> {
+    ProgramError::FilesystemLoop {
+        prg_name: "findr",
+        pathname,
+        ancestor: ancestor.to_path_buf(),
+    }
+}
+
+pub(crate) fn translate_error(e: Box<dyn error::Error>, pathname: &str) -> ProgramError<'_> {
     let params = ProgramErrorParams::new()
         .pathname(pathname)
         .program("findr")
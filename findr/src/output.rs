@@ -0,0 +1,74 @@
+use error_utils::ProgramError;
+use std::io::{self, BufWriter, IsTerminal, Write};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{LazyLock, Mutex, MutexGuard};
+
+/// Standard output, buffered unless it is a terminal, where each entry shows up as soon as it
+/// is found
+struct Stdout {
+    writer: BufWriter<io::Stdout>,
+    terminal: bool,
+}
+
+static STDOUT: LazyLock<Mutex<Stdout>> = LazyLock::new(|| {
+    Mutex::new(Stdout {
+        writer: BufWriter::new(io::stdout()),
+        terminal: io::stdout().is_terminal(),
+    })
+});
+
+fn stdout() -> MutexGuard<'static, Stdout> {
+    STDOUT
+        .lock()
+        .unwrap_or_else(|poisoned| poisoned.into_inner())
+}
+
+/// write the bytes to standard output as they are
+pub fn print(bytes: &[u8]) -> io::Result<()> {
+    let mut stdout = stdout();
+    let res = stdout.writer.write_all(bytes);
+    let res = match stdout.terminal {
+        true => res.and_then(|()| stdout.writer.flush()),
+        false => res,
+    };
+    quit_on_broken_pipe(res)
+}
+
+/// write out what standard output still buffers, before something else writes to the terminal
+pub fn flush() -> io::Result<()> {
+    quit_on_broken_pipe(stdout().writer.flush())
+}
+
+/// whether errors are reported as JSON objects, for `--format ndjson`
+static JSON_ERRORS: AtomicBool = AtomicBool::new(false);
+
+/// report the errors from now on as JSON objects giving their kind, path and message
+pub fn report_as_json() {
+    JSON_ERRORS.store(true, Ordering::Relaxed);
+}
+
+/// report the error on standard error, after the entries printed before it
+pub fn report(e: ProgramError) {
+    let _ = flush();
+    match JSON_ERRORS.load(Ordering::Relaxed) {
+        true => eprintln!(
+            "{}",
+            serde_json::json!({
+                "kind": e.kind(),
+                "path": e.pathname(),
+                "message": e.to_string(),
+            })
+        ),
+        false => eprintln!("{}", e),
+    }
+}
+
+/// Once the reader of the output is gone, as when piped into `headr`, nothing more can be
+/// printed, so findr stops without complaining, as find does when killed by SIGPIPE
+fn quit_on_broken_pipe(res: io::Result<()>) -> io::Result<()> {
+    match res {
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => process::exit(0),
+        res => res,
+    }
+}
@@ -0,0 +1,235 @@
+use crate::cli::{FindrArgs, SortKey, Symlinks};
+use crate::entry::Entry;
+use crate::expr::Expr;
+use crate::results::Limit;
+use crate::{output, report_walk_error, translate_loop};
+use ignore::{WalkBuilder, WalkState};
+use std::cmp::Ordering;
+use std::error;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Walk the path with `--jobs` threads, each taking directories from the others once it runs
+/// out. This walker also knows about ignore files, so it walks with a single thread as well
+/// when asked to respect them.
+///
+/// Each thread evaluates its own copy of the expression, so that `-exec ... +` gathers its
+/// batches per thread. When sorted, or when walking the contents of directories first, the
+/// entries are gathered first, then evaluated in the order walkdir finds them in, or in the
+/// order of the sort key
+pub fn process_path(p: &str, args: &FindrArgs, expr: &mut Expr, limit: &Limit) {
+    if args.sort.is_some() || expr.contents_first() {
+        return process_gathered(p, args, expr, limit);
+    }
+    let expr = &*expr;
+    walk(p, args, expr, || {
+        let mut worker = Worker(expr.clone());
+        move |entry| {
+            if !limit.eval(&mut worker.0, &entry) {
+                return WalkState::Quit;
+            }
+            match entry.is_pruned() {
+                true => WalkState::Skip,
+                false => WalkState::Continue,
+            }
+        }
+    });
+}
+
+/// gather the entries of the path, then evaluate them with each directory before its contents,
+/// or after them when walking contents first, unless sorted by another key than the path
+fn process_gathered(p: &str, args: &FindrArgs, expr: &mut Expr, limit: &Limit) {
+    let key = args.sort.unwrap_or(SortKey::Path);
+    // sorted by another key than the path, the contents of a directory can come before it, so
+    // the directories pruned are found while walking, and their contents never gathered
+    let prunes_early = key != SortKey::Path && !expr.contents_first() && expr.may_prune();
+    let entries = Mutex::new(vec![]);
+    let tests = &*expr;
+    walk(p, args, tests, || {
+        |entry: Entry| {
+            let skip = prunes_early && entry.file_type().is_dir() && tests.prunes(&entry);
+            entries
+                .lock()
+                .unwrap_or_else(|poisoned| poisoned.into_inner())
+                .push(entry);
+            match skip {
+                true => WalkState::Skip,
+                false => WalkState::Continue,
+            }
+        }
+    });
+    let mut entries = entries
+        .into_inner()
+        .unwrap_or_else(|poisoned| poisoned.into_inner());
+
+    if expr.contents_first() {
+        // the order of the walk puts each directory right before its contents
+        entries.sort_unstable_by(|a: &Entry, b| walk_order(a.path(), b.path(), args.reverse));
+        let mut dirs: Vec<Entry> = vec![];
+        for entry in entries {
+            while let Some(dir) = dirs.pop_if(|dir| !entry.path().starts_with(dir.path())) {
+                if !limit.eval(expr, &dir) {
+                    return;
+                }
+            }
+            match entry.file_type().is_dir() {
+                true => dirs.push(entry),
+                false => {
+                    if !limit.eval(expr, &entry) {
+                        return;
+                    }
+                }
+            }
+        }
+        while let Some(dir) = dirs.pop() {
+            if !limit.eval(expr, &dir) {
+                return;
+            }
+        }
+        return;
+    }
+
+    entries.sort_unstable_by(|a, b| match (key, args.reverse) {
+        (SortKey::Path, reverse) => walk_order(a.path(), b.path(), reverse),
+        (key, false) => key.compare(a, b),
+        (key, true) => key.compare(b, a),
+    });
+    // in the order of the walk, the contents of a pruned directory were walked, but are left
+    // alone as they come right after it
+    let mut pruned: Option<PathBuf> = None;
+    for entry in entries {
+        if pruned
+            .as_ref()
+            .is_some_and(|dir| entry.path().starts_with(dir))
+        {
+            continue;
+        }
+        if !limit.eval(expr, &entry) {
+            return;
+        }
+        if entry.is_pruned() && key == SortKey::Path {
+            pruned = Some(entry.path().to_path_buf());
+        }
+    }
+}
+
+/// Compare the paths in the order of a walk taking the entries of each directory in the order
+/// of their names, or the reverse order: component by component, which puts each directory
+/// right before its contents
+fn walk_order(a: &Path, b: &Path, reverse: bool) -> Ordering {
+    let (mut a, mut b) = (a.components(), b.components());
+    loop {
+        match (a.next(), b.next()) {
+            (Some(a), Some(b)) if a == b => continue,
+            (Some(a), Some(b)) if reverse => return b.cmp(&a),
+            (Some(a), Some(b)) => return a.cmp(&b),
+            (a, b) => return a.is_some().cmp(&b.is_some()),
+        }
+    }
+}
+
+/// The copy of the expression a thread evaluates, finished once the thread is done
+struct Worker(Expr);
+
+impl Drop for Worker {
+    fn drop(&mut self) {
+        self.0.finish();
+    }
+}
+
+/// walk the path, giving each entry within the depth limits to the visitor of its thread, which
+/// tells whether to walk the contents of the entry
+fn walk<F>(p: &str, args: &FindrArgs, expr: &Expr, mut new_visitor: impl FnMut() -> F)
+where
+    F: FnMut(Entry) -> WalkState + Send,
+{
+    let symlinks = args.symlinks();
+    let (min_depth, max_depth) = args.walk_depths();
+    // the ignore crate always follows a symbolic link given as the path to walk
+    if symlinks == Symlinks::None {
+        if let Some(link) = Entry::link(Path::new(p), 0) {
+            if min_depth.unwrap_or(0) == 0 {
+                new_visitor()(link);
+            }
+            return;
+        }
+    }
+    let mut builder = WalkBuilder::new(p);
+    builder
+        .standard_filters(false)
+        .threads(args.jobs.get())
+        .max_depth(max_depth)
+        .min_depth(min_depth)
+        .follow_links(symlinks == Symlinks::All)
+        .same_file_system(expr.stays_on_file_system());
+    if args.respect_ignore {
+        builder
+            .ignore(true)
+            .git_ignore(true)
+            .git_exclude(true)
+            .git_global(true)
+            .parents(true)
+            .filter_entry(|entry| entry.depth() == 0 || entry.file_name() != ".git");
+    }
+    builder.build_parallel().run(|| {
+        let mut visit = new_visitor();
+        Box::new(move |res| match res {
+            Ok(entry) => {
+                let follow = symlinks.follows(entry.depth());
+                visit(Entry::from(entry).follow_links(follow))
+            }
+            Err(e) => {
+                let broken_link = e
+                    .io_error()
+                    .filter(|io_err| io_err.kind() == io::ErrorKind::NotFound)
+                    .and(error_path(&e))
+                    .and_then(|path| Entry::link(path, e.depth().unwrap_or(0)));
+                match broken_link {
+                    Some(entry) => visit(entry),
+                    None => {
+                        report(e, p);
+                        WalkState::Continue
+                    }
+                }
+            }
+        })
+    });
+}
+
+/// report the error as walkdir would, at the path it happened at
+fn report(e: ignore::Error, root: &str) {
+    if let Some((child, ancestor)) = file_system_loop(&e) {
+        output::report(translate_loop(&child.to_string_lossy(), ancestor));
+        return;
+    }
+    let path = error_path(&e).map(Path::to_path_buf);
+    let e: Box<dyn error::Error> = match e.io_error() {
+        Some(io_err) => Box::new(io::Error::new(io_err.kind(), io_err.to_string())),
+        None => Box::new(e),
+    };
+    report_walk_error(e, path.as_deref(), root);
+}
+
+/// the path the ignore crate tags the error with
+fn error_path(e: &ignore::Error) -> Option<&Path> {
+    match e {
+        ignore::Error::WithPath { path, .. } => Some(path),
+        ignore::Error::WithDepth { err, .. } | ignore::Error::WithLineNumber { err, .. } => {
+            error_path(err)
+        }
+        ignore::Error::Loop { child, .. } => Some(child),
+        _ => None,
+    }
+}
+
+/// the link leading back to a directory it is within, and that directory
+fn file_system_loop(e: &ignore::Error) -> Option<(&Path, &Path)> {
+    match e {
+        ignore::Error::WithPath { err, .. }
+        | ignore::Error::WithDepth { err, .. }
+        | ignore::Error::WithLineNumber { err, .. } => file_system_loop(err),
+        ignore::Error::Loop { child, ancestor } => Some((child, ancestor)),
+        _ => None,
+    }
+}
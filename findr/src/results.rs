@@ -0,0 +1,197 @@
+use crate::entry::Entry;
+use crate::expr::Expr;
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+/// `--limit N`: the number of entries still to match before the walk stops, if limited
+#[derive(Debug)]
+pub struct Limit(Option<Mutex<usize>>);
+
+impl Limit {
+    pub fn new(max: Option<usize>) -> Self {
+        Self(max.map(Mutex::new))
+    }
+
+    /// Evaluate the expression on the entry, unless enough entries matched already, and return
+    /// whether to go on walking. Threads evaluate one at a time when limited, so that they never
+    /// match more entries than the limit between them
+    pub fn eval(&self, expr: &mut Expr, entry: &Entry) -> bool {
+        let Some(left) = &self.0 else {
+            expr.eval(entry);
+            return true;
+        };
+        let mut left = left.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        if *left > 0 && expr.eval(entry) {
+            *left -= 1;
+        }
+        *left > 0
+    }
+
+    pub fn is_reached(&self) -> bool {
+        self.0
+            .as_ref()
+            .is_some_and(|left| *left.lock().unwrap_or_else(|poisoned| poisoned.into_inner()) == 0)
+    }
+}
+
+/// What ranks the entries kept by [`Top`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Rank {
+    /// `--newest N`, by modification time
+    Newest,
+    /// `--largest N`, by size
+    Largest,
+}
+
+/// An entry kept by [`Top`], ranked by its time or size, then by its path for those tied, the
+/// first path ranking highest
+#[derive(Debug)]
+struct Ranked {
+    key: Key,
+    entry: Entry,
+}
+
+impl Ranked {
+    fn rank(&self) -> (&Key, Reverse<&Path>) {
+        (&self.key, Reverse(self.entry.path()))
+    }
+}
+
+impl PartialEq for Ranked {
+    fn eq(&self, other: &Self) -> bool {
+        self.rank() == other.rank()
+    }
+}
+
+impl Eq for Ranked {}
+
+impl PartialOrd for Ranked {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Ranked {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.rank().cmp(&other.rank())
+    }
+}
+
+#[derive(Debug, PartialEq, Eq, PartialOrd, Ord)]
+enum Key {
+    Time(SystemTime),
+    Size(u64),
+}
+
+/// `--newest N` or `--largest N`: the N entries matching the expression which rank highest,
+/// gathered by every thread walking the paths. The heap holding them drops the lowest one
+/// whenever it holds more than N, so that it never grows with the number of entries walked
+#[derive(Debug, Clone)]
+pub struct Top {
+    rank: Rank,
+    n: usize,
+    heap: Arc<Mutex<BinaryHeap<Reverse<Ranked>>>>,
+}
+
+impl Top {
+    pub fn new(rank: Rank, n: usize) -> Self {
+        Self {
+            rank,
+            n,
+            heap: Arc::new(Mutex::new(BinaryHeap::with_capacity(n + 1))),
+        }
+    }
+
+    /// keep the entry until N entries ranking higher are found, returning whether it ranks at
+    /// all, which it does not without metadata
+    pub fn keep(&self, entry: &Entry) -> bool {
+        let key = match self.rank {
+            Rank::Newest => entry
+                .metadata()
+                .and_then(|m| m.modified().ok())
+                .map(Key::Time),
+            Rank::Largest => entry.metadata().map(|m| Key::Size(m.len())),
+        };
+        let Some(key) = key else {
+            return false;
+        };
+        let mut heap = self.lock();
+        heap.push(Reverse(Ranked {
+            key,
+            entry: entry.clone(),
+        }));
+        if heap.len() > self.n {
+            heap.pop();
+        }
+        true
+    }
+
+    /// the entries kept, the highest ranking first unless reversed
+    pub fn entries(&self, reverse: bool) -> Vec<Entry> {
+        let heap = std::mem::take(&mut *self.lock());
+        // the heap orders the entries lowest first, by reversing their ranks
+        let mut entries: Vec<_> = heap
+            .into_sorted_vec()
+            .into_iter()
+            .map(|Reverse(ranked)| ranked.entry)
+            .collect();
+        if reverse {
+            entries.reverse();
+        }
+        entries
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, BinaryHeap<Reverse<Ranked>>> {
+        self.heap
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    fn entry(path: &Path) -> Entry {
+        let entry = walkdir::WalkDir::new(path).into_iter().next();
+        Entry::from(entry.expect("file exists").expect("file is readable"))
+    }
+
+    #[test]
+    fn keep_largest_entries() -> std::io::Result<()> {
+        let dir = tempfile::tempdir()?;
+        let top = Top::new(Rank::Largest, 2);
+        for (name, size) in [("a", 3), ("b", 1), ("c", 5), ("d", 3), ("e", 2)] {
+            let path = dir.path().join(name);
+            fs::write(&path, "x".repeat(size))?;
+            assert!(top.keep(&entry(&path)));
+            assert!(top.lock().len() <= 2, "holds no more than N entries");
+        }
+        let path = |name: &str| dir.path().join(name);
+        let paths: Vec<_> = top
+            .entries(false)
+            .iter()
+            .map(|e| e.path().to_path_buf())
+            .collect();
+        assert_eq!(paths, [path("c"), path("a")], "ties go by path");
+        Ok(())
+    }
+
+    #[test]
+    fn limit_matches() {
+        let limit = Limit::new(Some(2));
+        let (mut t, mut f) = (Expr::True, Expr::False);
+        let e = entry(Path::new("."));
+        assert!(limit.eval(&mut f, &e));
+        assert!(limit.eval(&mut t, &e));
+        assert!(!limit.is_reached());
+        assert!(!limit.eval(&mut t, &e));
+        assert!(limit.is_reached());
+        assert!(Limit::new(None).eval(&mut t, &e));
+        assert!(Limit::new(Some(0)).is_reached());
+    }
+}
@@ -0,0 +1,97 @@
+use crate::entry::Entry;
+use crate::expr::json;
+use crate::{output, translate_error};
+use std::collections::{HashMap, HashSet};
+use std::ops::RangeInclusive;
+#[cfg(unix)]
+use std::os::unix::fs::MetadataExt;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+/// The sizes added up so far
+#[derive(Debug, Default)]
+struct Totals {
+    /// the total size of the regular files found in each directory whose contents are still
+    /// being walked, subdirectories included
+    dirs: HashMap<PathBuf, u64>,
+    /// the device and inode numbers of the files with several hard links counted already
+    inodes: HashSet<(u64, u64)>,
+}
+
+/// `--du`: the sizes of the regular files in each directory, added up while walking the
+/// contents of directories first, so that each directory is printed with its total as soon as
+/// its contents are done
+#[derive(Debug, Clone)]
+pub struct Usage {
+    json: bool,
+    /// the depths of the directories printed, all of them added up whatever their depth
+    depths: RangeInclusive<usize>,
+    totals: Arc<Mutex<Totals>>,
+}
+
+impl Usage {
+    /// the usage printed as the size, a tab and the path, or as a JSON object with both, for
+    /// the directories within the depth limits
+    pub fn new(json: bool, min_depth: Option<usize>, max_depth: Option<usize>) -> Self {
+        Self {
+            json,
+            depths: min_depth.unwrap_or(0)..=max_depth.unwrap_or(usize::MAX),
+            totals: Arc::default(),
+        }
+    }
+
+    /// Add the size of a regular file to its directory, counting a file with several hard links
+    /// once, or print the total of a directory within the depth limits and add it to its own
+    /// directory. Always true
+    pub fn tally(&self, entry: &Entry) -> bool {
+        let mut totals = self
+            .totals
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner());
+        let size = match entry.file_type() {
+            file_type if file_type.is_dir() => {
+                let size = totals.dirs.remove(entry.path()).unwrap_or(0);
+                if self.depths.contains(&entry.depth()) {
+                    self.print(entry, size);
+                }
+                size
+            }
+            file_type if file_type.is_file() => match entry.metadata() {
+                #[cfg(unix)]
+                Some(m) if m.nlink() > 1 && !totals.inodes.insert((m.dev(), m.ino())) => 0,
+                Some(m) => m.len(),
+                None => 0,
+            },
+            _ => 0,
+        };
+        if let Some(dir) = entry.path().parent().filter(|_| entry.depth() > 0) {
+            *totals.dirs.entry(dir.to_path_buf()).or_default() += size;
+        }
+        true
+    }
+
+    /// write out the totals printed so far
+    pub fn finish(&self) {
+        if let Err(e) = output::flush() {
+            output::report(translate_error(Box::new(e), "standard output"));
+        }
+    }
+
+    fn print(&self, entry: &Entry, size: u64) {
+        let line = match self.json {
+            true => {
+                let object = serde_json::json!({ "path": json::path(entry.path()), "size": size });
+                format!("{}\n", object).into_bytes()
+            }
+            false => {
+                let mut line = format!("{}\t", size).into_bytes();
+                line.extend_from_slice(entry.path().as_os_str().as_encoded_bytes());
+                line.push(b'\n');
+                line
+            }
+        };
+        if let Err(e) = output::print(&line) {
+            output::report(translate_error(Box::new(e), "standard output"));
+        }
+    }
+}
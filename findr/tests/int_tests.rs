@@ -1,5 +1,9 @@
 use assert_cmd::Command;
 use predicates::prelude::*;
+use serde_json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::time::{Duration, SystemTime};
 
 const PRG: &'static str = "findr";
 
@@ -12,10 +16,10 @@ fn dies_bad_type() -> anyhow::Result<()> {
 }
 
 #[test]
-fn dies_bad_name_regex() -> anyhow::Result<()> {
+fn dies_bad_name_glob() -> anyhow::Result<()> {
     run_bad_arg_test(
-        &["--name", "*.csv"],
-        "error: invalid value '*.csv' for '--name [<NAME>...]'",
+        &["--name", "*.[csv"],
+        "error: invalid value '*.[csv' for '--name [<NAME>...]'",
     )
 }
 
@@ -154,7 +158,7 @@ fn find_by_multiple_types() -> anyhow::Result<()> {
 #[test]
 fn find_by_name() -> anyhow::Result<()> {
     run_stdout_test(
-        &["tests/inputs", "--name", ".*[.]csv"],
+        &["tests/inputs", "--name", "*.csv"],
         &mut vec![
             "tests/inputs/g.csv",
             "tests/inputs/a/b/b.csv",
@@ -166,7 +170,7 @@ fn find_by_name() -> anyhow::Result<()> {
 #[test]
 fn find_by_multiple_names() -> anyhow::Result<()> {
     run_stdout_test(
-        &["tests/inputs", "--name", ".*[.]csv", "--name", ".*[.]txt"],
+        &["tests/inputs", "--name", "*.csv", "--name", "*.txt"],
         &mut vec![
             "tests/inputs/g.csv",
             "tests/inputs/a/a.txt",
@@ -182,7 +186,7 @@ fn find_by_multiple_names() -> anyhow::Result<()> {
 #[cfg(not(windows))]
 fn find_symbolic_links_by_name() -> anyhow::Result<()> {
     run_stdout_test(
-        &["tests/inputs", "--name", ".*[.]csv", "--type", "l"],
+        &["tests/inputs", "--name", "*.csv", "--type", "l"],
         &mut vec!["tests/inputs/d/b.csv"],
     )
 }
@@ -190,11 +194,1315 @@ fn find_symbolic_links_by_name() -> anyhow::Result<()> {
 #[test]
 fn find_regular_files_by_name() -> anyhow::Result<()> {
     run_stdout_test(
-        &["tests/inputs", "--name", ".*[.]csv", "--type", "f"],
+        &["tests/inputs", "--name", "*.csv", "--type", "f"],
         &mut vec!["tests/inputs/g.csv", "tests/inputs/a/b/b.csv"],
     )
 }
 
+#[test]
+fn find_by_expression() -> anyhow::Result<()> {
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "(",
+            "-name",
+            "*.csv",
+            "-o",
+            "-type",
+            "d",
+            ")",
+            "-a",
+            "!",
+            "-name",
+            "[ab]",
+        ],
+        &mut [
+            "tests/inputs",
+            "tests/inputs/g.csv",
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c",
+            "tests/inputs/f",
+            "tests/inputs/d",
+            "tests/inputs/d/b.csv",
+            "tests/inputs/d/e",
+        ],
+    )?;
+    // -and binds tighter than -or, and is implied between expressions
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.txt",
+            "-or",
+            "-type",
+            "f",
+            "-name",
+            "*.mp3",
+        ],
+        &mut [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/a/b/c/c.mp3",
+            "tests/inputs/f/f.txt",
+            "tests/inputs/d/d.txt",
+            "tests/inputs/d/e/e.mp3",
+        ],
+    )
+}
+
+#[test]
+fn find_by_name_glob() -> anyhow::Result<()> {
+    // globs match the file name rather than any part of the path
+    run_stdout_test(&["tests/inputs", "-name", "b"], &mut ["tests/inputs/a/b"])?;
+    run_stdout_test(
+        &["tests/inputs", "-iname", "?.CSV"],
+        &mut [
+            "tests/inputs/g.csv",
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/d/b.csv",
+        ],
+    )
+}
+
+#[test]
+fn find_by_path_glob() -> anyhow::Result<()> {
+    run_stdout_test(
+        &["tests/inputs", "-path", "*/b/*"],
+        &mut [
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c",
+            "tests/inputs/a/b/c/c.mp3",
+        ],
+    )?;
+    run_stdout_test(
+        &["tests/inputs", "-ipath", "TESTS/*/D/*.T?V"],
+        &mut ["tests/inputs/d/d.tsv"],
+    )
+}
+
+#[test]
+fn find_by_regex() -> anyhow::Result<()> {
+    // regular expressions match the whole path
+    run_stdout_test(&["tests/inputs", "-regex", "[a-z]+[.]csv"], &mut [])?;
+    run_stdout_test(
+        &["tests/inputs", "-iregex", ".*/[A-C][.]CSV"],
+        &mut ["tests/inputs/a/b/b.csv", "tests/inputs/d/b.csv"],
+    )?;
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "-regextype",
+            "posix-basic",
+            "-regex",
+            ".*/\\(a\\|f\\)[.]txt",
+        ],
+        &mut ["tests/inputs/a/a.txt", "tests/inputs/f/f.txt"],
+    )
+}
+
+#[test]
+fn combine_expression_with_options() -> anyhow::Result<()> {
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "-not",
+            "-name",
+            "*.csv",
+            "--type",
+            "f",
+            "--maxdepth",
+            "2",
+        ],
+        &mut [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/f/f.txt",
+            "tests/inputs/d/d.txt",
+            "tests/inputs/d/d.tsv",
+        ],
+    )
+}
+
+#[test]
+fn dies_bad_expression() -> anyhow::Result<()> {
+    run_bad_arg_test(
+        &["tests/inputs", "-name", "a", "-nmae", "b"],
+        "error: unknown primary '-nmae'\n\n  -name a -nmae\n          ^^^^^",
+    )?;
+    run_bad_arg_test(
+        &["tests/inputs", "(", "-type", "d", "-o"],
+        "error: expected an expression after '-o'\n\n  ( -type d -o\n            ^^",
+    )?;
+    run_bad_arg_test(&["tests/inputs", "(", "-type", "d"], "error: unmatched '('")?;
+    run_bad_arg_test(
+        &["tests/inputs", "-type", "x"],
+        "error: invalid type 'x' for '-type', expected one of d, f, l",
+    )
+}
+
+#[test]
+fn execute_command_for_each_entry() -> anyhow::Result<()> {
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "-name",
+            "*.csv",
+            "-exec",
+            "echo",
+            "found:{}",
+            ";",
+        ],
+        &mut [
+            "found:tests/inputs/g.csv",
+            "found:tests/inputs/a/b/b.csv",
+            "found:tests/inputs/d/b.csv",
+        ],
+    )?;
+    // the exit status of the command is the result of the test
+    run_stdout_test(
+        &[
+            "tests/inputs/a",
+            "-exec",
+            "test",
+            "-d",
+            "{}",
+            ";",
+            "-o",
+            "-print",
+        ],
+        &mut [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c/c.mp3",
+        ],
+    )
+}
+
+#[test]
+fn execute_command_on_batches_of_entries() -> anyhow::Result<()> {
+    let res = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-name", "*.txt", "-exec", "echo", "{}", "+"])
+        .output()?;
+    assert!(res.status.success(), "command is successful");
+    let stdout = String::from_utf8(res.stdout)?;
+    let lines = stdout.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 1, "runs the command once");
+    let mut paths = lines[0].split(' ').collect::<Vec<_>>();
+    paths.sort();
+    assert_eq!(
+        paths,
+        [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/d/d.txt",
+            "tests/inputs/f/f.txt"
+        ]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(not(windows))]
+fn execute_command_in_directory_of_entry() -> anyhow::Result<()> {
+    run_stdout_test(
+        &[
+            "tests/inputs/a",
+            "-name",
+            "*.csv",
+            "-execdir",
+            "ls",
+            "{}",
+            ";",
+        ],
+        &mut ["./b.csv"],
+    )
+}
+
+#[test]
+#[cfg(not(windows))]
+fn execute_command_after_confirmation() -> anyhow::Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/f", "-type", "f", "-ok", "echo", "{}", ";"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stdout("tests/inputs/f/f.txt\n")
+        .stderr("< echo ... tests/inputs/f/f.txt > ? ");
+    Command::cargo_bin(PRG)?
+        .args([
+            "tests/inputs/f/f.txt",
+            "-ok",
+            "echo",
+            "{}",
+            ";",
+            "-o",
+            "-print",
+        ])
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stdout("tests/inputs/f/f.txt\n");
+    Ok(())
+}
+
+#[test]
+fn reports_commands_failing_to_run() -> anyhow::Result<()> {
+    run_stderr_test(
+        &["tests/inputs/f", "-exec", "blargh", "{}", ";"],
+        &[format!("{PRG}: blargh: File or directory not found").as_str()],
+    )
+}
+
+#[test]
+fn delete_entries() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("a/b"))?;
+    fs::write(dir.path().join("a/b/b.txt"), "b")?;
+    fs::write(dir.path().join("a/a.csv"), "a")?;
+    fs::write(dir.path().join("c.txt"), "c")?;
+
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["(", "-name", "a", "-o", "-path", "*/a/*", ")", "-delete"])
+        .assert()
+        .success()
+        .stdout("");
+    let remaining = fs::read_dir(dir.path())?
+        .map(|entry| Ok(entry?.file_name()))
+        .collect::<anyhow::Result<Vec<_>>>()?;
+    assert_eq!(
+        remaining,
+        ["c.txt"],
+        "deletes directories after their contents"
+    );
+    Ok(())
+}
+
+/// a directory holding an empty file, a 2000 byte file modified 3 days ago, a 10 byte file
+/// modified 90 minutes ago and an empty directory
+fn metadata_tree() -> anyhow::Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    let now = SystemTime::now();
+    fs::write(dir.path().join("empty.txt"), "")?;
+    fs::write(dir.path().join("old.txt"), [b'a'; 2000])?;
+    fs::File::options()
+        .write(true)
+        .open(dir.path().join("old.txt"))?
+        .set_modified(now - Duration::from_secs(3 * 24 * 60 * 60))?;
+    fs::write(dir.path().join("recent.txt"), "0123456789")?;
+    fs::File::options()
+        .write(true)
+        .open(dir.path().join("recent.txt"))?
+        .set_modified(now - Duration::from_secs(90 * 60))?;
+    fs::create_dir(dir.path().join("empty"))?;
+    Ok(dir)
+}
+
+/// the file names of the entries matching the expression in the directory
+fn find_names(dir: &Path, expr: &[&str]) -> anyhow::Result<Vec<String>> {
+    let res = Command::cargo_bin(PRG)?
+        .arg(dir)
+        .args(["--mindepth", "1"])
+        .args(expr)
+        .args(["-exec", "basename", "{}", ";"])
+        .output()?;
+    assert!(
+        res.status.success(),
+        "command is successful: {}",
+        String::from_utf8(res.stderr)?
+    );
+    let mut names = String::from_utf8(res.stdout)?
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    names.sort();
+    Ok(names)
+}
+
+#[test]
+fn find_by_size() -> anyhow::Result<()> {
+    let dir = metadata_tree()?;
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-size", "-1k"])?,
+        ["empty.txt"]
+    );
+    assert_eq!(find_names(dir.path(), &["-size", "4"])?, ["old.txt"]);
+    assert_eq!(find_names(dir.path(), &["-size", "10c"])?, ["recent.txt"]);
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-size", "+1c"])?,
+        ["old.txt", "recent.txt"]
+    );
+    Ok(())
+}
+
+#[test]
+fn find_by_modification_time() -> anyhow::Result<()> {
+    let dir = metadata_tree()?;
+    assert_eq!(find_names(dir.path(), &["-mtime", "+2"])?, ["old.txt"]);
+    assert_eq!(find_names(dir.path(), &["-mtime", "3"])?, ["old.txt"]);
+    assert_eq!(
+        find_names(dir.path(), &["-mmin", "+60", "-mmin", "-120"])?,
+        ["recent.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["-mtime", "0"])?,
+        ["empty", "empty.txt", "recent.txt"]
+    );
+    assert_eq!(
+        find_names(
+            dir.path(),
+            &["-newer", &dir.path().join("recent.txt").to_string_lossy()]
+        )?,
+        ["empty", "empty.txt"]
+    );
+    Ok(())
+}
+
+#[test]
+fn find_empty_entries() -> anyhow::Result<()> {
+    let dir = metadata_tree()?;
+    assert_eq!(find_names(dir.path(), &["-empty"])?, ["empty", "empty.txt"]);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn find_by_permissions() -> anyhow::Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = metadata_tree()?;
+    fs::set_permissions(
+        dir.path().join("empty.txt"),
+        fs::Permissions::from_mode(0o600),
+    )?;
+    fs::set_permissions(
+        dir.path().join("old.txt"),
+        fs::Permissions::from_mode(0o644),
+    )?;
+    fs::set_permissions(
+        dir.path().join("recent.txt"),
+        fs::Permissions::from_mode(0o755),
+    )?;
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-perm", "644"])?,
+        ["old.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-perm", "u=rw,go=r"])?,
+        ["old.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-perm", "-u+x"])?,
+        ["recent.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["-type", "f", "-perm", "/044"])?,
+        ["old.txt", "recent.txt"]
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn find_by_owner() -> anyhow::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = metadata_tree()?;
+    let uid = fs::metadata(dir.path())?.uid().to_string();
+    let gid = fs::metadata(dir.path())?.gid().to_string();
+    let all = ["empty", "empty.txt", "old.txt", "recent.txt"];
+    assert_eq!(find_names(dir.path(), &["-uid", &uid, "-gid", &gid])?, all);
+    assert_eq!(
+        find_names(dir.path(), &["-user", &uid, "-group", &gid])?,
+        all
+    );
+    assert!(find_names(dir.path(), &["-nouser"])?.is_empty());
+    run_bad_arg_test(
+        &["-user", "no-such-user"],
+        "error: unknown user 'no-such-user' for '-user'",
+    )
+}
+
+#[test]
+fn print_paths_separated_by_nul() -> anyhow::Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/d", "-name", "*.txt", "-print0"])
+        .assert()
+        .success()
+        .stdout("tests/inputs/d/d.txt\0");
+    Ok(())
+}
+
+#[test]
+fn print_with_format() -> anyhow::Result<()> {
+    let dir = metadata_tree()?;
+    Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-name", "*.txt", "-printf", "%f|%5s|%-6y|%.3f|%d|%P\\n"])
+        .assert()
+        .success()
+        .stdout(predicate::str::contains(
+            "old.txt| 2000|f     |old|1|old.txt\n",
+        ))
+        .stdout(predicate::str::contains(
+            "empty.txt|    0|f     |emp|1|empty.txt\n",
+        ));
+
+    let year = Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-name", "recent.txt", "-printf", "%TY %m %M %h\\n"])
+        .output()?;
+    let year = String::from_utf8(year.stdout)?;
+    let fields = year.split_whitespace().collect::<Vec<_>>();
+    assert!(fields[0].parse::<u32>()? >= 2024, "prints the year");
+    assert!(
+        fields[1].starts_with(['6', '4', '7']),
+        "prints the octal mode"
+    );
+    assert!(fields[2].starts_with("-rw"), "prints the symbolic mode");
+    assert_eq!(fields[3], dir.path().to_str().expect("is UTF-8"));
+    Ok(())
+}
+
+#[test]
+fn print_to_file() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("out.txt");
+    let out = out.to_str().expect("is UTF-8");
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/d", "-name", "*.txt", "-fprint", out])
+        .args(["-o", "-name", "*.tsv", "-fprintf", out, "tsv %f\\n"])
+        .assert()
+        .success()
+        .stdout("");
+    let mut printed = fs::read_to_string(out)?
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    printed.sort();
+    assert_eq!(printed, ["tests/inputs/d/d.txt", "tsv d.tsv"]);
+
+    run_bad_arg_test(
+        &["-fprint", "tests/no-such-dir/out.txt"],
+        "cannot create 'tests/no-such-dir/out.txt' for '-fprint'",
+    )?;
+    run_bad_arg_test(&["-printf", "%Q"], "unknown directive '%Q'")
+}
+
+#[test]
+#[cfg(unix)]
+fn print_non_utf8_names_unchanged() -> anyhow::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir()?;
+    let name = std::ffi::OsStr::from_bytes(b"caf\xe9\nmenu");
+    fs::write(dir.path().join(name), "")?;
+    let res = Command::cargo_bin(PRG)?
+        .args(["--mindepth", "1"])
+        .arg(dir.path())
+        .arg("-print0")
+        .output()?;
+    let mut expected = dir.path().join(name).into_os_string().into_encoded_bytes();
+    expected.push(0);
+    assert_eq!(res.stdout, expected);
+
+    let res = Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args(["-type", "f", "-printf", "[%f]"])
+        .output()?;
+    assert_eq!(res.stdout, b"[caf\xe9\nmenu]");
+    Ok(())
+}
+
+#[test]
+fn prints_nothing_without_matches() -> anyhow::Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-name", "no-such-name"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+fn prints_entries_in_order_with_command_output() -> anyhow::Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/d", "-name", "d.*", "-print"])
+        .args(["-exec", "echo", "ran", ";"])
+        .assert()
+        .success()
+        .stdout(predicate::str::is_match(
+            "^tests/inputs/d/d\\.(txt|tsv)\nran\ntests/inputs/d/d\\.(txt|tsv)\nran\n$",
+        )?);
+    Ok(())
+}
+
+#[test]
+fn stops_quietly_once_output_is_closed() -> anyhow::Result<()> {
+    use std::io::Read;
+    use std::process::Stdio;
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["tests/inputs", "-printf", "%100000p\\n"])
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+    let mut start = [0; 10];
+    child
+        .stdout
+        .take()
+        .expect("has standard output")
+        .read_exact(&mut start)?;
+    let res = child.wait_with_output()?;
+    assert!(res.status.success(), "exits successfully");
+    assert_eq!(String::from_utf8(res.stderr)?, "", "reports nothing");
+    Ok(())
+}
+
+#[test]
+fn walk_in_parallel() -> anyhow::Result<()> {
+    let sequential = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--sort"])
+        .output()?;
+    for jobs in [&["-j", "4"][..], &["--jobs=8"], &["-j2"]] {
+        Command::cargo_bin(PRG)?
+            .args(["tests/inputs", "--sort"])
+            .args(jobs)
+            .assert()
+            .success()
+            .stdout(String::from_utf8(sequential.stdout.clone())?);
+    }
+
+    run_stdout_test(
+        &[
+            "tests/inputs",
+            "-j",
+            "4",
+            "--mindepth",
+            "2",
+            "--maxdepth",
+            "2",
+            "-name",
+            "*.*",
+        ],
+        &mut [
+            "tests/inputs/a/a.txt",
+            "tests/inputs/d/b.csv",
+            "tests/inputs/d/d.tsv",
+            "tests/inputs/d/d.txt",
+            "tests/inputs/f/f.txt",
+        ],
+    )?;
+    run_stderr_test(
+        &["-j", "4", "tests/inputs/a", "blargh"],
+        &["findr: blargh: File or directory not found"],
+    )?;
+    run_bad_arg_test(&["-j", "0"], "invalid value '0'")
+}
+
+#[test]
+fn execute_batches_while_walking_in_parallel() -> anyhow::Result<()> {
+    let res = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-j", "4", "-type", "f"])
+        .args(["-exec", "echo", "{}", "+"])
+        .output()?;
+    assert!(res.status.success(), "command is successful");
+    let mut paths = String::from_utf8(res.stdout)?
+        .split_whitespace()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    paths.sort();
+    let mut files = regular_files();
+    files.sort();
+    assert_eq!(paths, files);
+    Ok(())
+}
+
+fn repository_tree() -> anyhow::Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    for subdir in [".git/info", "src", "target/debug", "sub"] {
+        fs::create_dir_all(dir.path().join(subdir))?;
+    }
+    fs::write(dir.path().join(".gitignore"), "target/\n")?;
+    fs::write(dir.path().join(".ignore"), "*.log\n")?;
+    fs::write(dir.path().join(".git/info/exclude"), "secret.rs\n")?;
+    for file in [
+        ".git/HEAD",
+        "src/main.rs",
+        "target/debug/build.rs",
+        "sub/lib.rs",
+        "sub/debug.log",
+        "secret.rs",
+    ] {
+        fs::write(dir.path().join(file), "")?;
+    }
+    Ok(dir)
+}
+
+#[test]
+fn skip_ignored_entries() -> anyhow::Result<()> {
+    let dir = repository_tree()?;
+    for jobs in ["1", "4"] {
+        let names = find_names(dir.path(), &["--respect-ignore", "-j", jobs])?;
+        assert_eq!(
+            names,
+            [".gitignore", ".ignore", "lib.rs", "main.rs", "src", "sub"],
+            "skips ignored entries and .git with {jobs} jobs"
+        );
+    }
+    let names = find_names(dir.path(), &["--respect-ignore", "--sort", "-name", "*.rs"])?;
+    assert_eq!(names, ["lib.rs", "main.rs"]);
+    let names = find_names(dir.path(), &["-name", "*.rs"])?;
+    assert_eq!(
+        names,
+        ["build.rs", "lib.rs", "main.rs", "secret.rs"],
+        "walks everything without --respect-ignore"
+    );
+    Ok(())
+}
+
+#[test]
+fn prune_directories() -> anyhow::Result<()> {
+    let dir = repository_tree()?;
+    let expr = ["(", "-name", "target", "-o", "-name", ".git", ")", "-prune"];
+    let expr = [&expr[..], &["-o", "-type", "f", "-name", "*.rs"]].concat();
+    for options in [&[][..], &["-j", "4"], &["-j", "4", "--sort"]] {
+        let names = find_names(dir.path(), &[options, &expr[..]].concat())?;
+        assert_eq!(
+            names,
+            ["lib.rs", "main.rs", "secret.rs"],
+            "skips pruned directories with {options:?}"
+        );
+    }
+
+    let res = Command::cargo_bin(PRG)?
+        .arg(dir.path())
+        .args([
+            "--respect-ignore",
+            "-name",
+            "sub",
+            "-prune",
+            "-o",
+            "-delete",
+        ])
+        .output()?;
+    assert!(res.status.success(), "command is successful");
+    assert!(
+        !dir.path().join("sub/lib.rs").exists(),
+        "ignores -prune when deleting contents first"
+    );
+    assert!(
+        dir.path().join("sub/debug.log").exists(),
+        "keeps ignored entries"
+    );
+    assert!(!dir.path().join("src").exists(), "deletes directories");
+    assert!(dir.path().join("target/debug/build.rs").exists());
+    Ok(())
+}
+
+#[cfg(unix)]
+fn symlink_tree() -> anyhow::Result<tempfile::TempDir> {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("tree/real"))?;
+    fs::create_dir(dir.path().join("tree/links"))?;
+    fs::write(dir.path().join("tree/real/file"), "")?;
+    symlink("../real", dir.path().join("tree/links/to_dir"))?;
+    symlink("nowhere", dir.path().join("tree/links/broken"))?;
+    symlink("tree/real", dir.path().join("root_link"))?;
+    Ok(dir)
+}
+
+/// the paths findr prints in the directory, relative to it and sorted
+fn find_paths(dir: &Path, args: &[&str]) -> anyhow::Result<Vec<String>> {
+    let res = Command::cargo_bin(PRG)?
+        .current_dir(dir)
+        .args(args)
+        .output()?;
+    assert!(
+        res.status.success(),
+        "command is successful: {}",
+        String::from_utf8(res.stderr)?
+    );
+    let mut paths = String::from_utf8(res.stdout)?
+        .lines()
+        .map(String::from)
+        .collect::<Vec<_>>();
+    paths.sort();
+    Ok(paths)
+}
+
+#[test]
+#[cfg(unix)]
+fn follow_symbolic_links() -> anyhow::Result<()> {
+    let dir = symlink_tree()?;
+    for jobs in ["1", "4"] {
+        let paths = find_paths(dir.path(), &["-j", jobs, "tree", "-type", "f"])?;
+        assert_eq!(paths, ["tree/real/file"], "-P is the default");
+        let paths = find_paths(dir.path(), &["-L", "-j", jobs, "tree", "-type", "f"])?;
+        assert_eq!(paths, ["tree/links/to_dir/file", "tree/real/file"]);
+        let paths = find_paths(dir.path(), &["-L", "-j", jobs, "tree", "-type", "l"])?;
+        assert_eq!(paths, ["tree/links/broken"], "tests broken links as links");
+        let paths = find_paths(dir.path(), &["-L", "-P", "-j", jobs, "tree", "-type", "l"])?;
+        assert_eq!(
+            paths,
+            ["tree/links/broken", "tree/links/to_dir"],
+            "the last option wins"
+        );
+
+        let paths = find_paths(dir.path(), &["-j", jobs, "root_link"])?;
+        assert_eq!(paths, ["root_link"], "-P does not follow paths");
+        let paths = find_paths(dir.path(), &["-H", "-j", jobs, "root_link", "tree/links"])?;
+        assert_eq!(
+            paths,
+            [
+                "root_link",
+                "root_link/file",
+                "tree/links",
+                "tree/links/broken",
+                "tree/links/to_dir"
+            ],
+            "-H only follows paths"
+        );
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn find_by_type_beyond_links() -> anyhow::Result<()> {
+    let dir = symlink_tree()?;
+    let paths = find_paths(dir.path(), &["tree", "-xtype", "d"])?;
+    assert_eq!(
+        paths,
+        ["tree", "tree/links", "tree/links/to_dir", "tree/real"]
+    );
+    let paths = find_paths(dir.path(), &["tree", "-xtype", "l"])?;
+    assert_eq!(paths, ["tree/links/broken"], "finds broken links");
+    let paths = find_paths(dir.path(), &["-L", "tree", "-xtype", "l"])?;
+    assert_eq!(paths, ["tree/links/broken", "tree/links/to_dir"]);
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn report_file_system_loops() -> anyhow::Result<()> {
+    let dir = symlink_tree()?;
+    std::os::unix::fs::symlink("..", dir.path().join("tree/links/loop"))?;
+    for jobs in ["1", "4"] {
+        Command::cargo_bin(PRG)?
+            .current_dir(dir.path())
+            .args(["-L", "-j", jobs, "tree", "-name", "file"])
+            .assert()
+            .success()
+            .stdout(predicate::str::contains("tree/real/file\n"))
+            .stderr("findr: tree/links/loop: File system loop detected, back to tree\n");
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn stay_on_file_system() -> anyhow::Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    // /proc is a file system of its own, mounted within the root one
+    if fs::metadata("/")?.dev() == fs::metadata("/proc")?.dev() {
+        return Ok(());
+    }
+    for jobs in ["1", "4"] {
+        for xdev in ["-xdev", "-mount"] {
+            Command::cargo_bin(PRG)?
+                .args(["/", "-j", jobs, "--maxdepth", "2", xdev, "-path", "/proc*"])
+                .assert()
+                .success()
+                .stdout("/proc\n");
+        }
+        Command::cargo_bin(PRG)?
+            .args(["/", "-j", jobs, "--maxdepth", "2", "-path", "/proc/self"])
+            .assert()
+            .success()
+            .stdout("/proc/self\n");
+    }
+    Ok(())
+}
+
+#[test]
+#[cfg(target_os = "linux")]
+fn find_by_file_system_type() -> anyhow::Result<()> {
+    Command::cargo_bin(PRG)?
+        .args([
+            "/proc",
+            "--maxdepth",
+            "1",
+            "-name",
+            "self",
+            "-fstype",
+            "proc",
+        ])
+        .assert()
+        .success()
+        .stdout("/proc/self\n");
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-fstype", "proc"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+#[test]
+#[cfg(unix)]
+fn find_special_files() -> anyhow::Result<()> {
+    use std::ffi::CString;
+    use std::os::unix::net::UnixListener;
+
+    let dir = tempfile::tempdir()?;
+    let fifo = CString::new(
+        dir.path()
+            .join("fifo")
+            .into_os_string()
+            .into_encoded_bytes(),
+    )?;
+    // SAFETY: the path is a valid NUL terminated string
+    assert_eq!(
+        unsafe { libc::mkfifo(fifo.as_ptr(), 0o644) },
+        0,
+        "creates fifo"
+    );
+    let _socket = UnixListener::bind(dir.path().join("socket"))?;
+    fs::write(dir.path().join("file"), "")?;
+    std::os::unix::fs::symlink("file", dir.path().join("link"))?;
+
+    assert_eq!(find_names(dir.path(), &["-type", "p"])?, ["fifo"]);
+    assert_eq!(find_names(dir.path(), &["-type", "s"])?, ["socket"]);
+    assert_eq!(
+        find_names(dir.path(), &["--type", "p,s"])?,
+        ["fifo", "socket"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["--type", "f,l", "--type", "s"])?,
+        ["file", "link", "socket"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["(", "-type", "f,p", "-o", "-type", "l", ")"])?,
+        ["fifo", "file", "link"]
+    );
+    run_stdout_test(&["/dev/null", "--type", "c"], &mut ["/dev/null"])?;
+    run_stdout_test(&["/dev/null", "-type", "b"], &mut [])?;
+    run_bad_arg_test(&["--type", "f,x"], "invalid value 'x' for '--type")
+}
+
+#[test]
+fn find_by_contents() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("sub/deeper"))?;
+    fs::write(dir.path().join("todo.txt"), "first\nTODO: write tests\n")?;
+    fs::write(dir.path().join("done.txt"), "nothing left to do\n")?;
+    fs::write(dir.path().join("notes.md"), "todo: read\n")?;
+    fs::write(dir.path().join("data.bin"), b"\x7fELF\0\nTODO\n")?;
+    fs::write(dir.path().join("sub/deeper/todo.rs"), "// TODO\n")?;
+
+    assert_eq!(
+        find_names(dir.path(), &["-contains", "TODO"])?,
+        ["todo.rs", "todo.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["-icontains", "^todo:"])?,
+        ["notes.md", "todo.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["--binary", "-contains", "TODO"])?,
+        ["data.bin", "todo.rs", "todo.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["--name", "*.txt", "-icontains", "todo"])?,
+        ["todo.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["--maxdepth", "1", "-contains", "TODO"])?,
+        ["todo.txt"]
+    );
+    assert_eq!(
+        find_names(dir.path(), &["--type", "d", "-contains", "."])?,
+        Vec::<String>::new()
+    );
+    assert_eq!(
+        find_names(dir.path(), &["!", "-contains", "TODO", "-type", "f"])?,
+        ["data.bin", "done.txt", "notes.md"]
+    );
+    run_bad_arg_test(
+        &["-contains", "a("],
+        "invalid regular expression 'a(' for '-contains'",
+    )
+}
+
+#[test]
+fn find_duplicates() -> anyhow::Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("sub"))?;
+    let large = "x".repeat(10_000);
+    fs::write(dir.path().join("a.txt"), "same")?;
+    fs::write(dir.path().join("sub/a_copy.txt"), "same")?;
+    fs::write(dir.path().join("diff.txt"), "diff")?;
+    fs::write(dir.path().join("large"), &large)?;
+    fs::write(dir.path().join("sub/large"), &large)?;
+    fs::write(dir.path().join("large_end"), format!("{}y", &large[1..]))?;
+    fs::write(dir.path().join("empty1"), "")?;
+    fs::write(dir.path().join("empty2"), "")?;
+    #[cfg(unix)]
+    fs::hard_link(
+        dir.path().join("diff.txt"),
+        dir.path().join("diff_link.txt"),
+    )?;
+
+    for jobs in ["1", "4"] {
+        let res = Command::cargo_bin(PRG)?
+            .current_dir(dir.path())
+            .args(["--duplicates", "-j", jobs])
+            .output()?;
+        assert!(res.status.success());
+        assert_eq!(
+            String::from_utf8(res.stdout)?,
+            "./a.txt\n./sub/a_copy.txt\n\n./large\n./sub/large\n"
+        );
+    }
+
+    let res = Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args(["--duplicates", "--min-size", "1k", "--json"])
+        .output()?;
+    assert_eq!(
+        String::from_utf8(res.stdout)?,
+        "{\"paths\":[\"./large\",\"./sub/large\"],\"size\":10000}\n"
+    );
+
+    let res = Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args(["--duplicates", "--min-size", "0", "--name", "*.txt"])
+        .output()?;
+    assert_eq!(
+        String::from_utf8(res.stdout)?,
+        "./a.txt\n./sub/a_copy.txt\n"
+    );
+    run_bad_arg_test(
+        &["tests/inputs", "--duplicates", "-printf", "P %p\n"],
+        "cannot be used with '--duplicates'",
+    )?;
+    run_bad_arg_test(&["--json"], "--duplicates")?;
+    run_bad_arg_test(&["--duplicates", "--min-size", "1x"], "invalid size '1x'")
+}
+
+fn sorted_tree() -> anyhow::Result<tempfile::TempDir> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("b"))?;
+    let start = SystemTime::now() - Duration::from_secs(3600);
+    for (i, (path, size)) in [("b/a", 30), ("c", 10), ("a", 20), ("b/d", 0)]
+        .into_iter()
+        .enumerate()
+    {
+        let file = fs::File::create(dir.path().join(path))?;
+        file.set_len(size)?;
+        file.set_modified(start + Duration::from_secs(60 * i as u64))?;
+    }
+    Ok(dir)
+}
+
+fn find_ordered(dir: &Path, args: &[&str]) -> anyhow::Result<Vec<String>> {
+    let res = Command::cargo_bin(PRG)?
+        .current_dir(dir)
+        .args(args)
+        .output()?;
+    assert!(
+        res.status.success(),
+        "command is successful: {}",
+        String::from_utf8(res.stderr)?
+    );
+    Ok(String::from_utf8(res.stdout)?
+        .lines()
+        .map(String::from)
+        .collect())
+}
+
+#[test]
+fn sort_entries() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    for jobs in ["1", "4"] {
+        let find = |args: &[&str]| find_ordered(dir, &[&["-j", jobs][..], args].concat());
+        assert_eq!(
+            find(&["--sort"])?,
+            [".", "./a", "./b", "./b/a", "./b/d", "./c"]
+        );
+        assert_eq!(
+            find(&["--sort", "path", "--reverse"])?,
+            [".", "./c", "./b", "./b/d", "./b/a", "./a"]
+        );
+        assert_eq!(
+            find(&["--sort", "name", "--type", "f"])?,
+            ["./a", "./b/a", "./c", "./b/d"]
+        );
+        assert_eq!(
+            find(&["--sort", "size", "--type", "f"])?,
+            ["./b/d", "./c", "./a", "./b/a"]
+        );
+        assert_eq!(
+            find(&["--sort", "mtime", "--reverse", "--type", "f"])?,
+            ["./b/d", "./a", "./c", "./b/a"]
+        );
+        assert_eq!(
+            find(&["--sort", "size", "--limit", "2", "--type", "f"])?,
+            ["./b/d", "./c"]
+        );
+        assert_eq!(
+            find(&["--sort", "path", "--reverse", "-depth"])?,
+            ["./c", "./b/d", "./b/a", "./b", "./a", "."]
+        );
+    }
+    run_bad_arg_test(
+        &["--sort", "size", "-depth"],
+        "-depth cannot be used with '--sort size'",
+    )?;
+    run_bad_arg_test(
+        &["--sort", "mtime", "--du"],
+        "--du cannot be used with '--sort mtime'",
+    )?;
+    Command::cargo_bin(PRG)?
+        .current_dir(dir)
+        .args(["--sort", "size", "-delete"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "-delete cannot be used with '--sort size'",
+        ));
+    assert!(dir.join("b/a").exists(), "deletes nothing");
+    run_bad_arg_test(&["--sort", "color"], "invalid value 'color' for '--sort")?;
+    run_bad_arg_test(&["--reverse"], "--sort")
+}
+
+#[test]
+fn prune_directories_sorted_by_key() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    let expr = ["-name", "b", "-prune", "-o", "-type", "f", "-print"];
+    for jobs in ["1", "4"] {
+        let find =
+            |args: &[&str]| find_ordered(dir, &[&["-j", jobs][..], args, &expr[..]].concat());
+        assert_eq!(find(&["--sort", "size"])?, ["./c", "./a"]);
+        assert_eq!(find(&["--sort", "name", "--reverse"])?, ["./c", "./a"]);
+        assert_eq!(find(&["--sort", "mtime"])?, ["./c", "./a"]);
+    }
+    Ok(())
+}
+
+#[test]
+fn limit_matches() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    assert_eq!(
+        find_ordered(dir, &["--sort", "--limit", "3"])?,
+        [".", "./a", "./b"]
+    );
+    assert_eq!(
+        find_ordered(dir, &["--sort", "--limit", "2", "-type", "f"])?,
+        ["./a", "./b/a"]
+    );
+    assert_eq!(
+        find_ordered(dir, &[".", "b", "--sort", "--limit", "7"])?.len(),
+        7,
+        "stops within the second path"
+    );
+    assert_eq!(find_ordered(dir, &["--limit", "0"])?, Vec::<String>::new());
+    for jobs in ["1", "4"] {
+        let paths = find_ordered(dir, &["--limit", "2", "-j", jobs, "-type", "f"])?;
+        assert_eq!(paths.len(), 2);
+    }
+    Ok(())
+}
+
+#[test]
+fn keep_newest_and_largest() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    for jobs in ["1", "4"] {
+        let find = |args: &[&str]| find_ordered(dir, &[&["-j", jobs][..], args].concat());
+        assert_eq!(find(&["--newest", "2", "--type", "f"])?, ["./b/d", "./a"]);
+        assert_eq!(
+            find(&["--newest", "2", "--type", "f", "--reverse"])?,
+            ["./a", "./b/d"]
+        );
+        assert_eq!(
+            find(&["--largest", "3", "-type", "f"])?,
+            ["./b/a", "./a", "./c"]
+        );
+        assert_eq!(find(&["--largest", "1", "-name", "[cd]"])?, ["./c"]);
+        assert_eq!(find(&["--largest", "0"])?, Vec::<String>::new());
+    }
+    run_bad_arg_test(
+        &["tests/inputs", "--largest", "2", "-printf", "%s %p\n"],
+        "cannot be used with '--largest'",
+    )?;
+    run_bad_arg_test(
+        &["tests/inputs", "--newest", "2", "-type", "f", "-print"],
+        "cannot be used with '--newest'",
+    )?;
+    run_bad_arg_test(&["--newest", "1", "--largest", "1"], "cannot be used with")
+}
+
+fn find_json(dir: &Path, args: &[&str]) -> anyhow::Result<(Vec<Value>, Vec<Value>)> {
+    let res = Command::cargo_bin(PRG)?
+        .current_dir(dir)
+        .args(["--format", "ndjson"])
+        .args(args)
+        .output()?;
+    let parse = |output: Vec<u8>| -> anyhow::Result<Vec<Value>> {
+        String::from_utf8(output)?
+            .lines()
+            .map(|line| Ok(serde_json::from_str(line)?))
+            .collect()
+    };
+    Ok((parse(res.stdout)?, parse(res.stderr)?))
+}
+
+#[test]
+fn print_json_objects() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    let (objects, errors) = find_json(dir, &["--sort", "-name", "a"])?;
+    assert_eq!(objects.len(), 2);
+    assert_eq!(errors, Vec::<Value>::new());
+    let a = &objects[0];
+    assert_eq!(a["path"], json!("./a"));
+    assert_eq!(a["type"], json!("f"));
+    assert_eq!(a["size"], json!(20));
+    assert_eq!(a["depth"], json!(1));
+    let mtime = fs::metadata(dir.join("a"))?
+        .modified()?
+        .duration_since(SystemTime::UNIX_EPOCH)?
+        .as_secs();
+    assert_eq!(a["mtime"], json!(mtime));
+    assert!(a["mode"].as_str().is_some_and(|mode| mode.len() == 3));
+    assert_eq!(objects[1]["path"], json!("./b/a"));
+    assert_eq!(objects[1]["depth"], json!(2));
+
+    let (objects, _) = find_json(
+        dir,
+        &["--fields", "path,depth", "--sort", "--maxdepth", "1"],
+    )?;
+    assert_eq!(
+        objects,
+        [
+            json!({ "path": ".", "depth": 0 }),
+            json!({ "path": "./a", "depth": 1 }),
+            json!({ "path": "./b", "depth": 1 }),
+            json!({ "path": "./c", "depth": 1 }),
+        ]
+    );
+    let (objects, _) = find_json(
+        dir,
+        &["--fields", "path,size", "--largest", "1", "-type", "f"],
+    )?;
+    assert_eq!(objects, [json!({ "path": "./b/a", "size": 30 })]);
+    assert_eq!(
+        find_ordered(dir, &["--format", "ndjson", "-name", "c", "-print"])?,
+        ["./c"],
+        "-print prints paths"
+    );
+    assert_eq!(
+        find_ordered(
+            dir,
+            &[
+                "--format",
+                "ndjson",
+                "--fields",
+                "size,path,depth",
+                "-name",
+                "c"
+            ]
+        )?,
+        [r#"{"size":10,"path":"./c","depth":1}"#],
+        "writes the fields in the order given"
+    );
+
+    let (objects, errors) = find_json(dir, &["--fields", "path", "c", "missing"])?;
+    assert_eq!(objects, [json!({ "path": "c" })]);
+    assert_eq!(
+        errors,
+        [json!({
+            "kind": "FileNotFound",
+            "path": "missing",
+            "message": "findr: missing: File or directory not found",
+        })]
+    );
+    run_bad_arg_test(&["--fields", "path"], "--format")?;
+    run_bad_arg_test(
+        &["--format", "ndjson", "--fields", "path,owner"],
+        "invalid value 'owner' for '--fields",
+    )
+}
+
+#[test]
+#[cfg(unix)]
+fn print_non_utf8_paths_in_json() -> anyhow::Result<()> {
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir()?;
+    let name = std::ffi::OsStr::from_bytes(b"caf\xe9");
+    fs::write(dir.path().join(name), "")?;
+    let (objects, _) = find_json(dir.path(), &["--fields", "path", "-type", "f"])?;
+    assert_eq!(objects, [json!({ "path": { "base64": "Li9jYWbp" } })]);
+    Ok(())
+}
+
+#[test]
+fn walk_contents_first() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    for jobs in ["1", "4"] {
+        let find = |args: &[&str]| find_ordered(dir, &[&["-j", jobs, "--sort"][..], args].concat());
+        assert_eq!(
+            find(&["-depth"])?,
+            ["./a", "./b/a", "./b/d", "./b", "./c", "."]
+        );
+        assert_eq!(
+            find(&["-depth", "(", "-name", "b", "-prune", "-o", "-print", ")"])?,
+            ["./a", "./b/a", "./b/d", "./c", "."],
+            "-prune skips nothing"
+        );
+        assert_eq!(
+            find(&["--maxdepth", "1", "-type", "d", "-depth"])?,
+            ["./b", "."]
+        );
+    }
+    Ok(())
+}
+
+#[test]
+fn add_up_directory_sizes() -> anyhow::Result<()> {
+    let dir = sorted_tree()?;
+    let dir = dir.path();
+    #[cfg(unix)]
+    fs::hard_link(dir.join("b/a"), dir.join("b/a_link"))?;
+    for jobs in ["1", "4"] {
+        let find = |args: &[&str]| find_ordered(dir, &[&["-j", jobs, "--sort"][..], args].concat());
+        assert_eq!(find(&["--du"])?, ["30\t./b", "60\t."]);
+        assert_eq!(find(&["--du", "-name", "a"])?, ["30\t./b", "50\t."]);
+        assert_eq!(find(&["--du", "--name", "c"])?, ["0\t./b", "10\t."]);
+        assert_eq!(find(&["--du", "--mindepth", "1"])?, ["30\t./b"]);
+        // the depth limits only leave directories unprinted, whatever they hold is added up
+        assert_eq!(find(&["--du", "--maxdepth", "1"])?, ["30\t./b", "60\t."]);
+        assert_eq!(find(&["--du", "--maxdepth", "0"])?, ["60\t."]);
+        assert_eq!(find(&["--du", "--mindepth", "2"])?, Vec::<String>::new());
+    }
+    let (objects, _) = find_json(dir, &["--du", "--sort"])?;
+    assert_eq!(
+        objects,
+        [
+            json!({ "path": "./b", "size": 30 }),
+            json!({ "path": ".", "size": 60 }),
+        ]
+    );
+    run_bad_arg_test(&["--du", "--duplicates"], "cannot be used with")
+}
+
 #[test]
 fn restrict_to_maxdepth() -> anyhow::Result<()> {
     run_stdout_test(
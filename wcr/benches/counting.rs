@@ -0,0 +1,49 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use tempfile::NamedTempFile;
+use wcr::Counter;
+
+/// size of the generated input, large enough for the cost of mapping the file to be negligible
+const INPUT_SIZE: usize = 64 * 1024 * 1024;
+
+fn generate_input() -> NamedTempFile {
+    let file = NamedTempFile::new().expect("creates input file");
+    let mut writer = BufWriter::new(file.as_file());
+    let line = "The  quick brown fox\tjumps over   the lazy dog. Frétt hefir öld óvu, þá er endr\n";
+    for _ in 0..INPUT_SIZE / line.len() {
+        writer
+            .write_all(line.as_bytes())
+            .expect("writes input line");
+    }
+    writer
+        .write_all(b"no trailing newline")
+        .expect("writes last line");
+    writer.flush().expect("flushes input");
+    drop(writer);
+    file
+}
+
+fn bench_counting(c: &mut Criterion) {
+    let input = generate_input();
+    let counter = Counter::new().bytes(true).lines(true).words(true);
+
+    let mut group = c.benchmark_group("count_file");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(
+        input.as_file().metadata().expect("reads metadata").len(),
+    ));
+    group.bench_function("read_line", |b| {
+        b.iter(|| {
+            let file = File::open(input.path()).expect("opens input");
+            counter.count_reader(file).expect("counts input")
+        })
+    });
+    group.bench_function("mmap", |b| {
+        b.iter(|| counter.count_file(input.path()).expect("counts input"))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_counting);
+criterion_main!(benches);
@@ -1,4 +1,5 @@
-use clap::Parser;
+use crate::check::Rule;
+use clap::{Parser, ValueEnum};
 
 #[derive(Debug, Parser)]
 #[command(version, about, long_about=None)]
@@ -19,6 +20,73 @@ pub struct Wcr {
     /// Show the character count
     #[arg(short('w'), long("words"))]
     pub show_word_count: bool,
+    /// Show the length of the longest line
+    #[arg(short('L'), long("max-line-length"))]
+    pub show_max_line_length: bool,
+    /// Show the N most frequent words with their counts
+    #[arg(long("top"), value_name("N"))]
+    pub top: Option<usize>,
+    /// Treat words differing only in case as the same word
+    #[arg(long("ignore-case"), requires("top"))]
+    pub ignore_case: bool,
+    /// Also report line endings, trailing whitespace, indentation, missing final newline and BOM
+    #[arg(long("eol-report"))]
+    pub eol_report: bool,
+    /// Only report files breaking a rule such as 'lines<=500' and fail if there are any.
+    /// Rules compare bytes, chars, lines, words or max-line-length using <, <=, >, >=, == or !=.
+    /// They apply to the totals as well when --total keeps them
+    #[arg(long("check"), value_name("RULE"), conflicts_with("watch"))]
+    pub checks: Vec<Rule>,
+    /// Print the counts of each file as JSON, e.g. to save a snapshot for --baseline
+    #[arg(long("json"), conflicts_with_all(["checks", "watch"]))]
+    pub json: bool,
+    /// Print the changes in counts since a snapshot saved with --json
+    #[arg(
+        long("baseline"),
+        value_name("FILE"),
+        conflicts_with_all(["checks", "watch", "json"])
+    )]
+    pub baseline: Option<String>,
+    /// When to print a line with the totals of all files read.
+    /// 'only' prints just the total counts, without a filename
+    #[arg(
+        long("total"),
+        value_name("WHEN"),
+        value_enum,
+        default_value_t = Total::Auto,
+        conflicts_with_all(["json", "baseline"])
+    )]
+    pub total: Total,
+    /// Count the decompressed contents of gzip, xz and zstd compressed inputs
+    #[arg(short('z'), long("decompress"), conflicts_with("watch"))]
+    pub decompress: bool,
+    /// Treat each input as a tar archive and count each of its files separately
+    #[arg(long("tar"), conflicts_with("watch"))]
+    pub tar: bool,
+    /// Keep running and print the counts again whenever the files grow or are truncated
+    #[arg(long("watch"))]
+    pub watch: bool,
+    /// Seconds to wait between checks for changes in watch mode
+    #[arg(
+        long("interval"),
+        value_name("SECONDS"),
+        default_value_t = 1.0,
+        requires("watch")
+    )]
+    pub interval: f64,
+}
+
+/// when the totals of all files are printed
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum Total {
+    /// when more than one file was read
+    Auto,
+    /// even when a single file was read
+    Always,
+    /// instead of the counts of each file
+    Only,
+    /// not at all
+    Never,
 }
 
 impl Wcr {
@@ -28,6 +96,7 @@ impl Wcr {
             self.show_char_count,
             self.show_line_count,
             self.show_word_count,
+            self.show_max_line_length,
         ]
         .iter()
         .all(|v| !v)
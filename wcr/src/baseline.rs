@@ -0,0 +1,133 @@
+use crate::check::Metric;
+use crate::counts::{
+    counter::Counter,
+    result::{FileCount, Result as CountResult},
+};
+use std::fmt;
+use std::fs::File;
+use std::io::BufReader;
+
+/// the order in which counts are shown, matching the count rows
+const METRICS: [Metric; 5] = [
+    Metric::Lines,
+    Metric::Words,
+    Metric::Bytes,
+    Metric::Chars,
+    Metric::MaxLineLength,
+];
+
+#[derive(Debug, PartialEq)]
+enum Status {
+    Changed,
+    Appeared,
+    Disappeared,
+    Total,
+}
+
+/// The change in the counts of a file since the baseline snapshot
+#[derive(Debug, PartialEq)]
+pub struct Delta {
+    filename: String,
+    changes: Vec<i64>,
+    status: Status,
+}
+
+impl fmt::Display for Delta {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut output = String::new();
+        for change in &self.changes {
+            output.push_str(&format!("{:>+6}", change));
+        }
+        write!(f, "{} {}", output, self.filename)?;
+        match self.status {
+            Status::Appeared => write!(f, " (appeared)"),
+            Status::Disappeared => write!(f, " (disappeared)"),
+            Status::Changed | Status::Total => Ok(()),
+        }
+    }
+}
+
+/// read a snapshot of counts saved with `--json`
+pub fn load(path: &str) -> anyhow::Result<Vec<FileCount>> {
+    let file = File::open(path).map_err(|e| anyhow::anyhow!("{}: {}", path, e))?;
+    serde_json::from_reader(BufReader::new(file))
+        .map_err(|e| anyhow::anyhow!("{}: invalid baseline: {}", path, e))
+}
+
+/// compute the change in each selected count for the files counted now and in the baseline.
+/// Files only present on one side are compared against zero counts
+pub fn compare(
+    res: &CountResult,
+    baseline: Vec<FileCount>,
+    counter: &Counter,
+) -> anyhow::Result<Vec<Delta>> {
+    let metrics = METRICS
+        .into_iter()
+        .filter(|metric| counter.computes(*metric))
+        .collect::<Vec<_>>();
+    let current = res.file_counts();
+    let mut deltas = vec![];
+
+    for file_info in &current {
+        let previous = baseline
+            .iter()
+            .find(|previous| previous.filename() == file_info.filename());
+        let status = if previous.is_some() {
+            Status::Changed
+        } else {
+            Status::Appeared
+        };
+        deltas.push(Delta {
+            filename: file_info.filename().to_string(),
+            changes: changes(&metrics, Some(file_info), previous)?,
+            status,
+        });
+    }
+    for previous in &baseline {
+        if current
+            .iter()
+            .all(|file_info| file_info.filename() != previous.filename())
+        {
+            deltas.push(Delta {
+                filename: previous.filename().to_string(),
+                changes: changes(&metrics, None, Some(previous))?,
+                status: Status::Disappeared,
+            });
+        }
+    }
+
+    if deltas.len() > 1 {
+        let totals = (0..metrics.len())
+            .map(|i| deltas.iter().map(|delta| delta.changes[i]).sum())
+            .collect();
+        deltas.push(Delta {
+            filename: "total".to_string(),
+            changes: totals,
+            status: Status::Total,
+        });
+    }
+    Ok(deltas)
+}
+
+fn changes(
+    metrics: &[Metric],
+    current: Option<&FileCount>,
+    previous: Option<&FileCount>,
+) -> anyhow::Result<Vec<i64>> {
+    metrics
+        .iter()
+        .map(|metric| {
+            let value = |file_info: Option<&FileCount>| match file_info {
+                None => Ok(0),
+                Some(file_info) => metric.value(file_info).map(|v| v as i64).ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "{}: the baseline has no {} count, save it with the same count options",
+                        file_info.filename(),
+                        metric.name()
+                    )
+                }),
+            };
+            Ok(value(current)? - value(previous)?)
+        })
+        .collect()
+}
@@ -0,0 +1,224 @@
+use crate::counts::result::FileCount;
+use std::fmt;
+use std::str::FromStr;
+
+/// A count that a rule can set a threshold on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Metric {
+    Bytes,
+    Chars,
+    Lines,
+    Words,
+    MaxLineLength,
+}
+
+impl Metric {
+    const NAMES: [(&'static str, Metric); 5] = [
+        ("bytes", Metric::Bytes),
+        ("chars", Metric::Chars),
+        ("lines", Metric::Lines),
+        ("words", Metric::Words),
+        ("max-line-length", Metric::MaxLineLength),
+    ];
+
+    pub fn name(&self) -> &'static str {
+        Self::NAMES
+            .iter()
+            .find(|(_, metric)| metric == self)
+            .map(|(name, _)| *name)
+            .expect("every metric has a name")
+    }
+
+    pub fn value(&self, file_info: &FileCount) -> Option<usize> {
+        match self {
+            Self::Bytes => file_info.bytes(),
+            Self::Chars => file_info.chars(),
+            Self::Lines => file_info.lines(),
+            Self::Words => file_info.words(),
+            Self::MaxLineLength => file_info.max_line_length(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+    Equal,
+    NotEqual,
+}
+
+impl Comparison {
+    // two character operators come first so that `<=` is not read as `<`
+    const SYMBOLS: [(&'static str, Comparison); 6] = [
+        ("<=", Comparison::LessOrEqual),
+        (">=", Comparison::GreaterOrEqual),
+        ("==", Comparison::Equal),
+        ("!=", Comparison::NotEqual),
+        ("<", Comparison::Less),
+        (">", Comparison::Greater),
+    ];
+
+    fn symbol(&self) -> &'static str {
+        Self::SYMBOLS
+            .iter()
+            .find(|(_, comparison)| comparison == self)
+            .map(|(symbol, _)| *symbol)
+            .expect("every comparison has a symbol")
+    }
+
+    fn holds(&self, value: usize, limit: usize) -> bool {
+        match self {
+            Self::Less => value < limit,
+            Self::LessOrEqual => value <= limit,
+            Self::Greater => value > limit,
+            Self::GreaterOrEqual => value >= limit,
+            Self::Equal => value == limit,
+            Self::NotEqual => value != limit,
+        }
+    }
+}
+
+/// A threshold that every file must satisfy, such as `lines<=500`
+#[derive(Debug, Clone, PartialEq)]
+pub struct Rule {
+    pub metric: Metric,
+    comparison: Comparison,
+    limit: usize,
+}
+
+impl Rule {
+    /// the value of the rule's count for the file if it breaks the rule
+    pub fn violated_by(&self, file_info: &FileCount) -> Option<usize> {
+        self.metric
+            .value(file_info)
+            .filter(|value| !self.comparison.holds(*value, self.limit))
+    }
+}
+
+impl FromStr for Rule {
+    type Err = String;
+
+    fn from_str(rule: &str) -> Result<Self, Self::Err> {
+        let rule = rule.trim();
+        let operator_start = rule
+            .find(['<', '>', '=', '!'])
+            .ok_or_else(|| format!("missing comparison in '{}', e.g. 'lines<=500'", rule))?;
+        let (name, rest) = rule.split_at(operator_start);
+
+        let name = name.trim();
+        let metric = Metric::NAMES
+            .iter()
+            .find(|(metric_name, _)| *metric_name == name)
+            .map(|(_, metric)| *metric)
+            .ok_or_else(|| {
+                let names = Metric::NAMES.map(|(metric_name, _)| metric_name);
+                format!(
+                    "unknown count '{}', expected one of {}",
+                    name,
+                    names.join(", ")
+                )
+            })?;
+        let (comparison, limit) = Comparison::SYMBOLS
+            .iter()
+            .find_map(|(symbol, comparison)| {
+                rest.strip_prefix(symbol)
+                    .map(|limit| (*comparison, limit.trim()))
+            })
+            .ok_or_else(|| format!("invalid comparison in '{}'", rule))?;
+        let limit = limit
+            .parse()
+            .map_err(|_| format!("invalid limit '{}' in '{}'", limit, rule))?;
+
+        Ok(Self {
+            metric,
+            comparison,
+            limit,
+        })
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{}{}{}",
+            self.metric.name(),
+            self.comparison.symbol(),
+            self.limit
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Comparison, Metric, Rule};
+    use crate::counts::counter::Counter;
+
+    #[test]
+    fn parse_rules() {
+        assert_eq!(
+            "lines<=500".parse::<Rule>(),
+            Ok(Rule {
+                metric: Metric::Lines,
+                comparison: Comparison::LessOrEqual,
+                limit: 500
+            }),
+            "parses two character comparison"
+        );
+        assert_eq!(
+            " max-line-length > 120 ".parse::<Rule>(),
+            Ok(Rule {
+                metric: Metric::MaxLineLength,
+                comparison: Comparison::Greater,
+                limit: 120
+            }),
+            "parses rule with spaces"
+        );
+        assert_eq!(
+            "lines".parse::<Rule>(),
+            Err("missing comparison in 'lines', e.g. 'lines<=500'".to_string()),
+            "rejects rule without comparison"
+        );
+        assert_eq!(
+            "pages<10".parse::<Rule>(),
+            Err(
+                "unknown count 'pages', expected one of bytes, chars, lines, words, max-line-length"
+                    .to_string()
+            ),
+            "rejects unknown count"
+        );
+        assert_eq!(
+            "words=>10".parse::<Rule>(),
+            Err("invalid comparison in 'words=>10'".to_string()),
+            "rejects unknown comparison"
+        );
+        assert_eq!(
+            "bytes<=1k".parse::<Rule>(),
+            Err("invalid limit '1k' in 'bytes<=1k'".to_string()),
+            "rejects invalid limit"
+        );
+    }
+
+    #[test]
+    fn find_violations() -> anyhow::Result<()> {
+        let counts = Counter::new()
+            .lines(true)
+            .max_line_length(true)
+            .count_reader("short\na much longer line\n".as_bytes())?;
+
+        let rule: Rule = "lines<=2".parse().unwrap();
+        assert_eq!(rule.violated_by(&counts), None, "passes rule");
+        let rule: Rule = "max-line-length<10".parse().unwrap();
+        assert_eq!(rule.violated_by(&counts), Some(18), "breaks rule");
+        let rule: Rule = "words<1".parse().unwrap();
+        assert_eq!(
+            rule.violated_by(&counts),
+            None,
+            "ignores counts not computed"
+        );
+        Ok(())
+    }
+}
@@ -1,87 +1,278 @@
+use crate::counts::counter::Counter;
 use crate::counts::result::{FileCount, Result as CountResult, ResultItem};
 use crate::Wcr;
 use anyhow;
+use std::borrow::Cow;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use std::io::{self, BufRead, BufReader, Seek, SeekFrom};
 
-mod result;
+mod archive;
+mod compression;
+pub mod counter;
+pub mod eol;
+#[cfg(feature = "mmap")]
+pub mod mapped;
+pub mod result;
 
 /// compute the byte, char, line and word counts for all files or standard input items
 pub fn compute(wcr: &Wcr) -> anyhow::Result<CountResult> {
+    let counter = Counter::from(wcr);
     let mut res = CountResult::new();
-    let mut totals = FileCount::new("total", wcr);
+    let mut totals = FileCount::new("total", &counter);
 
     for filename in &wcr.files {
-        match open(filename) {
+        match open(filename, wcr) {
             Err(e) => res.add_item(ResultItem::Err {
                 filename: filename.to_string(),
                 msg: e.to_string(),
             }),
-            Ok(mut handle) => {
-                let file_info = get_counts(&wcr, filename, &mut handle)?;
-                res.add_item(ResultItem::Data(file_info.clone()));
-                totals = totals + file_info;
+            #[cfg(feature = "mmap")]
+            Ok(Input::Mapped(map)) => match get_slice_counts(&counter, filename, &map) {
+                Err(e) => res.add_item(ResultItem::Err {
+                    filename: filename.to_string(),
+                    msg: e.to_string(),
+                }),
+                Ok(file_info) => {
+                    res.add_item(ResultItem::Data(file_info.clone()));
+                    totals = totals + file_info;
+                }
+            },
+            Ok(Input::Reader(handle)) if wcr.tar => {
+                match archive::get_member_counts(&counter, filename, handle) {
+                    Err(e) => res.add_item(ResultItem::Err {
+                        filename: filename.to_string(),
+                        msg: e.to_string(),
+                    }),
+                    Ok(member_items) => {
+                        for item in member_items {
+                            if let ResultItem::Data(file_info) = &item {
+                                totals = totals + file_info.clone();
+                            }
+                            res.add_item(item);
+                        }
+                    }
+                }
             }
+            Ok(Input::Reader(mut handle)) => match get_counts(&counter, filename, &mut handle) {
+                Err(e) => res.add_item(ResultItem::Err {
+                    filename: filename.to_string(),
+                    msg: e.to_string(),
+                }),
+                Ok(file_info) => {
+                    res.add_item(ResultItem::Data(file_info.clone()));
+                    totals = totals + file_info;
+                }
+            },
         }
     }
-    res.add_totals(ResultItem::Data(totals));
+    res.add_totals(ResultItem::Data(totals), wcr.total);
     Ok(res)
 }
 
-/// Open the file or stdin
-fn open(filename: &str) -> anyhow::Result<Box<dyn BufRead>> {
-    match filename {
-        "-" => Ok(Box::new(BufReader::new(io::stdin().lock()))),
-        file => Ok(Box::new(BufReader::new(File::open(file)?))),
+/// An opened input, either read through a buffer or mapped into memory
+enum Input {
+    Reader(Box<dyn BufRead>),
+    #[cfg(feature = "mmap")]
+    Mapped(memmap2::Mmap),
+}
+
+/// Open the file or stdin, decoding compressed contents if requested. Regular files whose
+/// contents are counted as is are mapped into memory when the `mmap` feature is enabled
+fn open(filename: &str, wcr: &Wcr) -> anyhow::Result<Input> {
+    #[cfg(feature = "mmap")]
+    if filename != "-" && !wcr.decompress && !wcr.tar {
+        if let Some(map) = mapped::map(filename)? {
+            return Ok(Input::Mapped(map));
+        }
+    }
+
+    let handle: Box<dyn BufRead> = match filename {
+        "-" => Box::new(BufReader::new(io::stdin().lock())),
+        file => Box::new(BufReader::new(File::open(file)?)),
+    };
+    if wcr.decompress {
+        Ok(Input::Reader(compression::decode(handle)?))
+    } else {
+        Ok(Input::Reader(handle))
     }
 }
 
 /// compute the counts for a given file or standard input item
-fn get_counts(wcr: &Wcr, filename: &str, reader: &mut impl BufRead) -> anyhow::Result<FileCount> {
-    let mut file_info = FileCount::new(filename, wcr);
+pub fn get_counts(
+    counter: &Counter,
+    filename: &str,
+    reader: &mut impl BufRead,
+) -> anyhow::Result<FileCount> {
+    let mut file_info = FileCount::new(filename, counter);
+    update_counts(counter, &mut file_info, reader)?;
+    Ok(file_info)
+}
+
+/// compute the counts of contents already in memory, such as a memory mapped file
+#[cfg(feature = "mmap")]
+pub fn get_slice_counts(
+    counter: &Counter,
+    filename: &str,
+    contents: &[u8],
+) -> anyhow::Result<FileCount> {
+    let mut file_info = FileCount::new(filename, counter);
+    for line in contents.split_inclusive(|byte| *byte == b'\n') {
+        // fail on invalid UTF-8 the same way as `read_line` does
+        let line = std::str::from_utf8(line).map_err(|_| {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                "stream did not contain valid UTF-8",
+            )
+        })?;
+        count_line(counter, &mut file_info, line);
+    }
+    Ok(file_info)
+}
 
+/// bring the counts of a watched file up to date, reading only the bytes appended since the
+/// previous read. Counting starts over if the file was replaced, truncated, or rewritten without
+/// growing. Returns whether the counts changed
+pub fn refresh(counter: &Counter, file_info: &mut FileCount) -> anyhow::Result<bool> {
+    let mut file = File::open(file_info.filename())?;
+    let metadata = file.metadata()?;
+    let (len, inode, modified) = (metadata.len(), inode(&metadata), metadata.modified().ok());
+    let state = file_info.read_state();
+    let reset = inode != state.inode
+        || len < state.offset
+        || (len == state.offset && modified != state.modified);
+    if !reset && len == state.offset {
+        return Ok(false);
+    }
+    if reset {
+        *file_info = FileCount::new(file_info.filename(), counter);
+    }
+    file.seek(SeekFrom::Start(file_info.read_state().offset))?;
+    update_counts(counter, file_info, &mut BufReader::new(file))?;
+    let mut state = file_info.read_state().clone();
+    state.inode = inode;
+    state.modified = modified;
+    file_info.set_read_state(state);
+    Ok(true)
+}
+
+/// the inode number of the file, where the platform has them
+fn inode(metadata: &std::fs::Metadata) -> Option<u64> {
+    #[cfg(unix)]
+    return Some(std::os::unix::fs::MetadataExt::ino(metadata));
+    #[cfg(not(unix))]
+    None
+}
+
+/// add the counts of the remaining input to `file_info`, resuming the line and word at which the
+/// previous read of the same input stopped
+fn update_counts(
+    counter: &Counter,
+    file_info: &mut FileCount,
+    reader: &mut impl BufRead,
+) -> anyhow::Result<()> {
     let mut line = String::new();
     loop {
         let num_bytes = reader.read_line(&mut line)?;
         if num_bytes == 0 {
             break;
         }
-        if wcr.show_byte_count {
-            file_info.increment_byte_count(num_bytes);
-        }
-        if wcr.show_char_count {
-            file_info.increment_char_count(line.chars().count());
+        count_line(counter, file_info, &line);
+        line.clear();
+    }
+
+    Ok(())
+}
+
+/// add the counts of a line, including its line ending if it has one, to `file_info`
+fn count_line(counter: &Counter, file_info: &mut FileCount, line: &str) {
+    let mut state = file_info.read_state().clone();
+
+    if counter.bytes {
+        file_info.increment_byte_count(line.len());
+    }
+    if counter.chars {
+        file_info.increment_char_count(line.chars().count());
+    }
+    if counter.lines && !state.ends_mid_line {
+        file_info.increment_line_count(1);
+    }
+    if counter.max_line_length {
+        let line_length = line.trim_end_matches(['\n', '\r']).chars().count();
+        let line_length = if state.ends_mid_line {
+            state.partial_line_length + line_length
+        } else {
+            line_length
+        };
+        file_info.update_max_line_length(line_length);
+        state.partial_line_length = line_length;
+    }
+    if let Some(report) = file_info.eol_report_mut() {
+        report.record_line(line, state.offset == 0, state.ends_mid_line);
+    }
+
+    let mut words = line
+        .split_whitespace()
+        .map(Cow::Borrowed)
+        .collect::<Vec<_>>();
+    // a word split across two reads is counted once, as the joined word
+    let continued_word = match &state.partial_word {
+        Some(partial) if line.starts_with(|c: char| !c.is_whitespace()) => {
+            words[0] = Cow::Owned(format!("{}{}", partial, words[0]));
+            Some(partial)
         }
-        if wcr.show_line_count {
-            file_info.increment_line_count(1);
+        _ => None,
+    };
+    if counter.words {
+        file_info.increment_word_count(words.len() - usize::from(continued_word.is_some()));
+    }
+    if counter.top.is_some() {
+        if let Some(partial) = continued_word {
+            file_info.decrement_word_frequency(&word_key(counter, partial));
         }
-        if wcr.show_word_count {
-            file_info.increment_word_count(line.split_whitespace().count());
+        for word in &words {
+            file_info.increment_word_frequency(&word_key(counter, word));
         }
-        line.clear();
     }
 
-    Ok(file_info)
+    state.offset += line.len() as u64;
+    state.ends_mid_line = !line.ends_with('\n');
+    state.partial_word = if line.ends_with(|c: char| !c.is_whitespace()) {
+        words.last().map(|word| word.to_string())
+    } else {
+        None
+    };
+    file_info.set_read_state(state);
+}
+
+/// the key under which a word is recorded in the word frequency table
+fn word_key<'a>(counter: &Counter, word: &'a str) -> Cow<'a, str> {
+    if counter.ignore_case {
+        Cow::Owned(word.to_lowercase())
+    } else {
+        Cow::Borrowed(word)
+    }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::args::Wcr;
+    use crate::counts::counter::Counter;
     use crate::counts::result::FileCount;
-    use std::io::Cursor;
+    use std::io::{Cursor, Seek, Write};
+    use std::time::{Duration, SystemTime};
+    use tempfile::NamedTempFile;
 
     const COUNT_TEXT: &str = "It all happened quickly, she said.\n Out of nowhere the agent retorted  in Chinese 闭嘴吧\r\n";
 
     #[test]
     fn count_all() {
-        let mut wcr = create_args("tests/inputs/test.txt");
-        wcr.show_byte_count = true;
-        wcr.show_char_count = true;
-        wcr.show_line_count = true;
-        wcr.show_word_count = true;
+        let counter = Counter::new()
+            .bytes(true)
+            .chars(true)
+            .lines(true)
+            .words(true);
 
         run_count_test(
-            &wcr,
+            &counter,
             COUNT_TEXT,
             FileCount::with_counts(
                 "tests/inputs/test.txt",
@@ -96,41 +287,248 @@ mod tests {
 
     #[test]
     fn count_with_options() {
-        let mut wcr = create_args("tests/inputs/test.txt");
-        wcr.show_char_count = true;
-        wcr.show_word_count = true;
+        let counter = Counter::new().chars(true).words(true);
 
         run_count_test(
-            &wcr,
+            &counter,
             COUNT_TEXT,
             FileCount::with_counts("tests/inputs/test.txt", None, Some(87), None, Some(15)),
             "count chars and words",
         );
 
-        let mut wcr = create_args("tests/inputs/test.txt");
-        wcr.show_line_count = true;
+        let counter = Counter::new().lines(true);
         run_count_test(
-            &wcr,
+            &counter,
             COUNT_TEXT,
             FileCount::with_counts("tests/inputs/test.txt", None, None, Some(2), None),
             "count chars and words",
         );
     }
 
-    fn create_args(filename: &str) -> Wcr {
-        return Wcr {
-            files: vec![filename.to_string()],
-            show_byte_count: false,
-            show_char_count: false,
-            show_line_count: false,
-            show_word_count: false,
-        };
+    #[test]
+    fn count_word_frequencies() {
+        let text = "the cat and The dog\nand the bird\n";
+        let counter = Counter::new().top(2);
+        run_count_test(
+            &counter,
+            text,
+            FileCount::with_counts("tests/inputs/test.txt", None, None, None, None).with_top_words(
+                2,
+                &[
+                    ("the", 2),
+                    ("cat", 1),
+                    ("and", 2),
+                    ("The", 1),
+                    ("dog", 1),
+                    ("bird", 1),
+                ],
+            ),
+            "count case sensitive word frequencies",
+        );
+
+        let counter = counter.ignore_case(true);
+        run_count_test(
+            &counter,
+            text,
+            FileCount::with_counts("tests/inputs/test.txt", None, None, None, None).with_top_words(
+                2,
+                &[("the", 3), ("cat", 1), ("and", 2), ("dog", 1), ("bird", 1)],
+            ),
+            "count case insensitive word frequencies",
+        );
+    }
+
+    #[test]
+    fn merge_word_frequencies_for_totals() {
+        let first = FileCount::with_counts("total", None, None, None, Some(3))
+            .with_top_words(2, &[("a", 2), ("b", 1)]);
+        let second = FileCount::with_counts("b.txt", None, None, None, Some(3))
+            .with_top_words(2, &[("b", 2), ("c", 1)]);
+
+        let totals = first + second;
+        assert_eq!(
+            totals,
+            FileCount::with_counts("total", None, None, None, Some(6))
+                .with_top_words(2, &[("a", 2), ("b", 3), ("c", 1)]),
+            "merges counts and word frequencies"
+        );
+        assert_eq!(
+            totals.top_words(),
+            vec![("b", 3), ("a", 2)],
+            "returns most frequent words first"
+        );
+    }
+
+    #[test]
+    fn resume_counts_across_reads() {
+        let counter = Counter::new()
+            .bytes(true)
+            .lines(true)
+            .words(true)
+            .max_line_length(true)
+            .top(3);
+
+        let expected = super::get_counts(
+            &counter,
+            "tests/inputs/test.txt",
+            &mut Cursor::new(COUNT_TEXT),
+        )
+        .unwrap();
+        // split in the middle of a word, between words and right after a newline
+        for split_at in [9, 16, 35] {
+            let (first, second) = COUNT_TEXT.split_at(split_at);
+            let mut counts =
+                super::get_counts(&counter, "tests/inputs/test.txt", &mut Cursor::new(first))
+                    .unwrap();
+            super::update_counts(&counter, &mut counts, &mut Cursor::new(second)).unwrap();
+
+            assert_eq!(counts, expected, "resumes counting at byte {}", split_at);
+            assert_eq!(
+                counts.read_state().offset,
+                COUNT_TEXT.len() as u64,
+                "records the offset reached when split at byte {}",
+                split_at
+            );
+        }
+    }
+
+    #[test]
+    fn refresh_reads_appended_bytes_and_recounts_rewritten_files() -> anyhow::Result<()> {
+        let mut file = NamedTempFile::new()?;
+        let filename = file.path().to_string_lossy().to_string();
+        let counter = Counter::new().lines(true).words(true);
+        let mut counts = FileCount::new(&filename, &counter);
+
+        write!(file, "one two")?;
+        assert!(
+            super::refresh(&counter, &mut counts)?,
+            "counts new file contents"
+        );
+        assert!(
+            !super::refresh(&counter, &mut counts)?,
+            "skips unchanged file"
+        );
+        write!(file, "three\nfour\n")?;
+        assert!(
+            super::refresh(&counter, &mut counts)?,
+            "counts appended bytes"
+        );
+        assert_eq!(
+            counts,
+            FileCount::with_counts(&filename, None, None, Some(2), Some(3)),
+            "counts the word split across writes once"
+        );
+
+        file.as_file().set_len(0)?;
+        file.as_file_mut().rewind()?;
+        writeln!(file, "five")?;
+        assert!(
+            super::refresh(&counter, &mut counts)?,
+            "recounts truncated file"
+        );
+        assert_eq!(
+            counts,
+            FileCount::with_counts(&filename, None, None, Some(1), Some(1)),
+            "starts over after truncation"
+        );
+
+        file.as_file_mut().rewind()?;
+        write!(file, "vier")?;
+        file.as_file()
+            .set_modified(SystemTime::now() + Duration::from_secs(1))?;
+        assert!(
+            super::refresh(&counter, &mut counts)?,
+            "recounts file rewritten in place"
+        );
+        assert_eq!(
+            counts,
+            FileCount::with_counts(&filename, None, None, Some(1), Some(1)),
+            "starts over after a rewrite without growth"
+        );
+
+        let mut replacement = NamedTempFile::new_in(file.path().parent().unwrap())?;
+        write!(replacement, "six seven\neight nine ten\n")?;
+        replacement.persist(file.path())?;
+        assert!(
+            super::refresh(&counter, &mut counts)?,
+            "recounts replaced file"
+        );
+        assert_eq!(
+            counts,
+            FileCount::with_counts(&filename, None, None, Some(2), Some(5)),
+            "starts over when the file is replaced, though it grew past the offset"
+        );
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn count_mapped_files_like_buffered_reads() -> anyhow::Result<()> {
+        let counter = Counter::new()
+            .bytes(true)
+            .chars(true)
+            .lines(true)
+            .words(true)
+            .max_line_length(true)
+            .eol_report(true)
+            .top(3);
+        let texts = [
+            COUNT_TEXT,
+            "no trailing newline",
+            "two lines\nthe last without a newline",
+            "ends with a carriage return\r",
+            "\n\n",
+        ];
+
+        for text in texts {
+            let mut file = NamedTempFile::new()?;
+            write!(file, "{}", text)?;
+            let filename = file.path().to_string_lossy().to_string();
+
+            let map = super::mapped::map(&filename)?.expect("maps regular file");
+            assert_eq!(
+                super::get_slice_counts(&counter, &filename, &map)?,
+                super::get_counts(&counter, &filename, &mut Cursor::new(text))?,
+                "counts {:?} the same way as buffered reads",
+                text
+            );
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(feature = "mmap")]
+    fn skip_mapping_empty_files_and_fail_on_invalid_utf8() -> anyhow::Result<()> {
+        let mut file = NamedTempFile::new()?;
+        let filename = file.path().to_string_lossy().to_string();
+        assert!(super::mapped::map(&filename)?.is_none(), "skips empty file");
+
+        file.write_all(b"valid\n\xff invalid\n")?;
+        let map = super::mapped::map(&filename)?.expect("maps regular file");
+        let err = super::get_slice_counts(&Counter::new().lines(true), &filename, &map)
+            .expect_err("fails on invalid UTF-8");
+        assert_eq!(err.to_string(), "stream did not contain valid UTF-8");
+        Ok(())
+    }
+
+    #[test]
+    #[cfg(all(unix, feature = "mmap"))]
+    fn count_files_whose_paths_are_not_utf8() -> anyhow::Result<()> {
+        use std::ffi::OsStr;
+        use std::os::unix::ffi::OsStrExt;
+
+        let dir = tempfile::tempdir()?;
+        let path = dir.path().join(OsStr::from_bytes(b"caf\xe9"));
+        std::fs::write(&path, "one two\n")?;
+        let counts = Counter::new().lines(true).words(true).count_file(&path)?;
+        assert_eq!(counts.lines(), Some(1));
+        assert_eq!(counts.words(), Some(2));
+        Ok(())
     }
 
-    fn run_count_test(wcr: &Wcr, text: &str, expected: FileCount, desc: &str) {
+    fn run_count_test(counter: &Counter, text: &str, expected: FileCount, desc: &str) {
         let mut reader = Cursor::new(text);
-        let filename = wcr.files.get(0).unwrap();
-        let counts = super::get_counts(&wcr, filename, &mut reader);
+        let counts = super::get_counts(counter, "tests/inputs/test.txt", &mut reader);
 
         assert!(counts.is_ok(), "{}: returns ok", desc);
         let counts = counts.unwrap();
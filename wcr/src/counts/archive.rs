@@ -0,0 +1,57 @@
+use crate::counts::{counter::Counter, result::ResultItem};
+use std::io::BufRead;
+#[cfg(feature = "tar")]
+use std::io::BufReader;
+
+/// Compute the counts of each regular file in a tar archive, naming each `archive:member`. A
+/// member that cannot be read gets an error of its own while the others are still counted, and
+/// an archive that cannot be read further ends with an error named after it
+#[cfg(feature = "tar")]
+pub fn get_member_counts(
+    counter: &Counter,
+    filename: &str,
+    reader: Box<dyn BufRead>,
+) -> anyhow::Result<Vec<ResultItem>> {
+    let mut archive = tar::Archive::new(reader);
+    let mut member_items = vec![];
+
+    for entry in archive.entries()? {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                member_items.push(ResultItem::Err {
+                    filename: filename.to_string(),
+                    msg: e.to_string(),
+                });
+                break;
+            }
+        };
+        if !entry.header().entry_type().is_file() {
+            continue;
+        }
+        let name = format!(
+            "{}:{}",
+            filename,
+            String::from_utf8_lossy(&entry.path_bytes())
+        );
+        member_items.push(
+            match super::get_counts(counter, &name, &mut BufReader::new(entry)) {
+                Ok(file_info) => ResultItem::Data(file_info),
+                Err(e) => ResultItem::Err {
+                    filename: name,
+                    msg: e.to_string(),
+                },
+            },
+        );
+    }
+    Ok(member_items)
+}
+
+#[cfg(not(feature = "tar"))]
+pub fn get_member_counts(
+    _counter: &Counter,
+    _filename: &str,
+    _reader: Box<dyn BufRead>,
+) -> anyhow::Result<Vec<ResultItem>> {
+    anyhow::bail!("tar support is not enabled in this build")
+}
@@ -0,0 +1,97 @@
+use std::io::BufRead;
+#[cfg(any(feature = "gzip", feature = "xz", feature = "zstd"))]
+use std::io::BufReader;
+
+/// Compression formats recognised by the magic bytes at the start of the compressed data
+#[derive(Debug, PartialEq)]
+enum Compression {
+    Gzip,
+    Xz,
+    Zstd,
+}
+
+impl Compression {
+    fn detect(header: &[u8]) -> Option<Self> {
+        if header.starts_with(&[0x1f, 0x8b]) {
+            Some(Self::Gzip)
+        } else if header.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+            Some(Self::Xz)
+        } else if header.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+            Some(Self::Zstd)
+        } else {
+            None
+        }
+    }
+}
+
+/// wrap the reader in a decoder when its contents are compressed, otherwise return it as is
+pub fn decode(mut reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    match Compression::detect(reader.fill_buf()?) {
+        None => Ok(reader),
+        Some(Compression::Gzip) => gzip(reader),
+        Some(Compression::Xz) => xz(reader),
+        Some(Compression::Zstd) => zstd(reader),
+    }
+}
+
+#[cfg(feature = "gzip")]
+fn gzip(reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(
+        flate2::bufread::MultiGzDecoder::new(reader),
+    )))
+}
+
+#[cfg(not(feature = "gzip"))]
+fn gzip(_reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    anyhow::bail!("gzip support is not enabled in this build")
+}
+
+#[cfg(feature = "xz")]
+fn xz(reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(
+        xz2::bufread::XzDecoder::new_multi_decoder(reader),
+    )))
+}
+
+#[cfg(not(feature = "xz"))]
+fn xz(_reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    anyhow::bail!("xz support is not enabled in this build")
+}
+
+#[cfg(feature = "zstd")]
+fn zstd(reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    Ok(Box::new(BufReader::new(zstd::Decoder::with_buffer(
+        reader,
+    )?)))
+}
+
+#[cfg(not(feature = "zstd"))]
+fn zstd(_reader: Box<dyn BufRead>) -> anyhow::Result<Box<dyn BufRead>> {
+    anyhow::bail!("zstd support is not enabled in this build")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Compression;
+
+    #[test]
+    fn detect_compression_from_magic_bytes() {
+        assert_eq!(
+            Compression::detect(&[0x1f, 0x8b, 0x08, 0x00]),
+            Some(Compression::Gzip),
+            "detects gzip"
+        );
+        assert_eq!(
+            Compression::detect(&[0xfd, b'7', b'z', b'X', b'Z', 0x00, 0x00]),
+            Some(Compression::Xz),
+            "detects xz"
+        );
+        assert_eq!(
+            Compression::detect(&[0x28, 0xb5, 0x2f, 0xfd, 0x24]),
+            Some(Compression::Zstd),
+            "detects zstd"
+        );
+        assert_eq!(Compression::detect(b"The quick"), None, "plain text");
+        assert_eq!(Compression::detect(&[]), None, "empty input");
+    }
+}
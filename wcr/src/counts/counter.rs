@@ -0,0 +1,115 @@
+use crate::check::Metric;
+use crate::counts::{self, result::FileCount};
+use crate::Wcr;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// Selects the counts to compute for each input
+///
+/// ```
+/// let counts = wcr::Counter::new()
+///     .lines(true)
+///     .words(true)
+///     .count_reader("The quick brown fox\n".as_bytes())?;
+/// assert_eq!(counts.lines(), Some(1));
+/// assert_eq!(counts.words(), Some(4));
+/// assert_eq!(counts.bytes(), None);
+/// # Ok::<(), anyhow::Error>(())
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct Counter {
+    pub(crate) bytes: bool,
+    pub(crate) chars: bool,
+    pub(crate) lines: bool,
+    pub(crate) words: bool,
+    pub(crate) max_line_length: bool,
+    pub(crate) top: Option<usize>,
+    pub(crate) ignore_case: bool,
+    pub(crate) eol_report: bool,
+}
+
+impl Counter {
+    /// a counter that computes no counts until some are selected
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn bytes(mut self, enabled: bool) -> Self {
+        self.bytes = enabled;
+        self
+    }
+    pub fn chars(mut self, enabled: bool) -> Self {
+        self.chars = enabled;
+        self
+    }
+    pub fn lines(mut self, enabled: bool) -> Self {
+        self.lines = enabled;
+        self
+    }
+    pub fn words(mut self, enabled: bool) -> Self {
+        self.words = enabled;
+        self
+    }
+    /// find the length in characters of the longest line, excluding its line ending
+    pub fn max_line_length(mut self, enabled: bool) -> Self {
+        self.max_line_length = enabled;
+        self
+    }
+    /// build a word frequency table and report the `count` most frequent words
+    pub fn top(mut self, count: usize) -> Self {
+        self.top = Some(count);
+        self
+    }
+    /// record words differing only in case as the same word in the frequency table
+    pub fn ignore_case(mut self, enabled: bool) -> Self {
+        self.ignore_case = enabled;
+        self
+    }
+    /// report line endings, trailing whitespace, indentation, missing final newline and BOM
+    pub fn eol_report(mut self, enabled: bool) -> Self {
+        self.eol_report = enabled;
+        self
+    }
+    pub(crate) fn computes(&self, metric: Metric) -> bool {
+        match metric {
+            Metric::Bytes => self.bytes,
+            Metric::Chars => self.chars,
+            Metric::Lines => self.lines,
+            Metric::Words => self.words,
+            Metric::MaxLineLength => self.max_line_length,
+        }
+    }
+    /// compute the counts of everything read from `reader`, named `-` like standard input
+    pub fn count_reader(&self, reader: impl Read) -> anyhow::Result<FileCount> {
+        counts::get_counts(self, "-", &mut BufReader::new(reader))
+    }
+    /// compute the counts of the file at `path`, mapping it into memory when the `mmap` feature
+    /// is enabled and the file is a regular file
+    pub fn count_file(&self, path: impl AsRef<Path>) -> anyhow::Result<FileCount> {
+        let path = path.as_ref();
+        let filename = path.to_string_lossy();
+        #[cfg(feature = "mmap")]
+        if let Some(map) = counts::mapped::map(path)? {
+            return counts::get_slice_counts(self, &filename, &map);
+        }
+        let mut reader = BufReader::new(File::open(path)?);
+        counts::get_counts(self, &filename, &mut reader)
+    }
+}
+
+// the counts that rules are checked against are computed even when they are not shown
+impl From<&Wcr> for Counter {
+    fn from(wcr: &Wcr) -> Self {
+        let checks = |metric| wcr.checks.iter().any(|rule| rule.metric == metric);
+        Self {
+            bytes: wcr.show_byte_count || checks(Metric::Bytes),
+            chars: wcr.show_char_count || checks(Metric::Chars),
+            lines: wcr.show_line_count || checks(Metric::Lines),
+            words: wcr.show_word_count || checks(Metric::Words),
+            max_line_length: wcr.show_max_line_length || checks(Metric::MaxLineLength),
+            top: wcr.top,
+            ignore_case: wcr.ignore_case,
+            eol_report: wcr.eol_report,
+        }
+    }
+}
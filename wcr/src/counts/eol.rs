@@ -0,0 +1,158 @@
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::ops::Add;
+
+/// Line ending and whitespace diagnostics for an input. The `missing_final_newline` and `bom`
+/// fields are 1 for an input that has them, so that totals count the inputs affected
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EolReport {
+    pub lf: usize,
+    pub crlf: usize,
+    pub cr: usize,
+    pub trailing_whitespace: usize,
+    pub tab_indented: usize,
+    pub space_indented: usize,
+    pub mixed_indented: usize,
+    pub missing_final_newline: usize,
+    pub bom: usize,
+}
+
+impl EolReport {
+    pub const HEADER: &'static str =
+        "     LF   CRLF     CR  TRAIL    TAB  SPACE  MIXED  NOEOL    BOM FILE";
+
+    /// record the line endings and whitespace of a line returned by `read_line`. `starts_input`
+    /// is set for the first line of the input and `continues_line` when the previous read stopped
+    /// in the middle of this line
+    pub fn record_line(&mut self, line: &str, starts_input: bool, continues_line: bool) {
+        let mut content = line;
+        if starts_input {
+            if let Some(rest) = content.strip_prefix('\u{feff}') {
+                self.bom = 1;
+                content = rest;
+            }
+        }
+        if let Some(rest) = content.strip_suffix("\r\n") {
+            self.crlf += 1;
+            content = rest;
+        } else if let Some(rest) = content.strip_suffix('\n') {
+            self.lf += 1;
+            content = rest;
+        }
+        self.missing_final_newline = usize::from(!line.ends_with('\n'));
+
+        // a carriage return that is not part of a CRLF ends a line of its own
+        for (i, segment) in content.split('\r').enumerate() {
+            if i > 0 {
+                self.cr += 1;
+            }
+            if segment.ends_with([' ', '\t']) {
+                self.trailing_whitespace += 1;
+            }
+            if i > 0 || !continues_line {
+                self.record_indentation(segment);
+            }
+        }
+    }
+
+    fn record_indentation(&mut self, segment: &str) {
+        let text = segment.trim_start_matches([' ', '\t']);
+        if text.is_empty() {
+            return;
+        }
+        let indentation = &segment[..segment.len() - text.len()];
+        match (indentation.contains('\t'), indentation.contains(' ')) {
+            (true, true) => self.mixed_indented += 1,
+            (true, false) => self.tab_indented += 1,
+            (false, true) => self.space_indented += 1,
+            (false, false) => {}
+        }
+    }
+}
+
+impl fmt::Display for EolReport {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}",
+            self.lf,
+            self.crlf,
+            self.cr,
+            self.trailing_whitespace,
+            self.tab_indented,
+            self.space_indented,
+            self.mixed_indented,
+            self.missing_final_newline,
+            self.bom
+        )
+    }
+}
+
+impl Add for EolReport {
+    type Output = EolReport;
+
+    fn add(self, other: Self) -> Self {
+        Self {
+            lf: self.lf + other.lf,
+            crlf: self.crlf + other.crlf,
+            cr: self.cr + other.cr,
+            trailing_whitespace: self.trailing_whitespace + other.trailing_whitespace,
+            tab_indented: self.tab_indented + other.tab_indented,
+            space_indented: self.space_indented + other.space_indented,
+            mixed_indented: self.mixed_indented + other.mixed_indented,
+            missing_final_newline: self.missing_final_newline + other.missing_final_newline,
+            bom: self.bom + other.bom,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EolReport;
+
+    #[test]
+    fn record_line_endings_and_whitespace() {
+        let mut report = EolReport::default();
+        let lines = [
+            "\u{feff}first line\r\n",
+            "\tsecond  \n",
+            "  third\rfourth\n",
+            " \tmixed",
+        ];
+        for (i, line) in lines.iter().enumerate() {
+            report.record_line(line, i == 0, false);
+        }
+
+        assert_eq!(
+            report,
+            EolReport {
+                lf: 2,
+                crlf: 1,
+                cr: 1,
+                trailing_whitespace: 1,
+                tab_indented: 1,
+                space_indented: 1,
+                mixed_indented: 1,
+                missing_final_newline: 1,
+                bom: 1,
+            }
+        );
+    }
+
+    #[test]
+    fn record_continued_line() {
+        let mut report = EolReport::default();
+        report.record_line("  indented", true, false);
+        report.record_line("\tmore\n", false, true);
+
+        assert_eq!(
+            report,
+            EolReport {
+                lf: 1,
+                space_indented: 1,
+                ..EolReport::default()
+            },
+            "does not treat continued line as indented"
+        );
+    }
+}
@@ -0,0 +1,21 @@
+use memmap2::Mmap;
+use std::fs::File;
+use std::io;
+use std::path::Path;
+
+/// map the contents of a regular file into memory, or return `None` for files that cannot be
+/// mapped, such as pipes, special files and empty files
+pub fn map(path: impl AsRef<Path>) -> io::Result<Option<Mmap>> {
+    let file = File::open(path)?;
+    let metadata = file.metadata()?;
+    if !metadata.is_file() || metadata.len() == 0 {
+        return Ok(None);
+    }
+    // SAFETY: the map is only read while counting. If another process truncates the file at the
+    // same time, reading the pages past its new end raises SIGBUS and kills wcr, a risk accepted
+    // for the speed up on large files. Watch mode, where files being appended to and rotated are
+    // expected, reads the files instead of mapping them
+    let map = unsafe { Mmap::map(&file) };
+    // fall back to reading the file if it cannot be mapped, e.g. on some network filesystems
+    Ok(map.ok())
+}
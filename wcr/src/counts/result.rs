@@ -1,40 +1,153 @@
-use crate::Wcr;
+use crate::args::Total;
+use crate::check::Rule;
+use crate::counts::{counter::Counter, eol::EolReport};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
 use std::fmt;
 use std::ops::Add;
+use std::time::SystemTime;
 
 pub struct Result {
     items: Vec<ResultItem>,
+    totals: Option<ResultItem>,
+    /// whether only the totals are printed, without the counts of each file
+    totals_only: bool,
 }
 
 impl Result {
     pub fn new() -> Self {
-        Self { items: vec![] }
+        Self {
+            items: vec![],
+            totals: None,
+            totals_only: false,
+        }
     }
 
     pub fn print(&self) {
-        for item in &self.items {
+        if self.totals_only {
+            self.print_errors();
+            if let Some(ResultItem::Data(totals)) = &self.totals {
+                println!("{}", totals.counts_only());
+            }
+            Self::print_eol_reports(self.totals.iter());
+            return;
+        }
+        for item in self.items.iter().chain(&self.totals) {
             match item {
-                ResultItem::Data(_) => println!("{}", item),
+                ResultItem::Data(_) | ResultItem::Violation { .. } => println!("{}", item),
                 ResultItem::Err { .. } => eprintln!("{}", item),
             }
         }
+        Self::print_eol_reports(self.items.iter().chain(&self.totals));
+    }
+
+    /// print the line ending report of each item with counts, if one was computed, below a header
+    fn print_eol_reports<'a>(items: impl Iterator<Item = &'a ResultItem>) {
+        let eol_reports = items
+            .filter_map(|item| match item {
+                ResultItem::Data(file_info) => file_info
+                    .eol_report()
+                    .map(|report| (report, file_info.filename())),
+                ResultItem::Err { .. } | ResultItem::Violation { .. } => None,
+            })
+            .collect::<Vec<_>>();
+        if !eol_reports.is_empty() {
+            println!();
+            println!("{}", EolReport::HEADER);
+            for (report, filename) in eol_reports {
+                println!("{} {}", report, filename);
+            }
+        }
     }
 
     pub fn add_item(&mut self, item: ResultItem) {
         self.items.push(item);
     }
 
-    pub fn add_totals(&mut self, totals: ResultItem) {
-        if self.items.len() > 1 {
-            self.items.push(totals);
+    /// keep the totals of the files read if `mode` asks for them, ignoring files that failed
+    pub fn add_totals(&mut self, totals: ResultItem, mode: Total) {
+        let show = match mode {
+            Total::Auto => self.file_counts().len() > 1,
+            Total::Always | Total::Only => true,
+            Total::Never => false,
+        };
+        if show {
+            self.totals = Some(totals);
+        }
+        self.totals_only = mode == Total::Only;
+    }
+
+    /// replace the counts of each file, and the totals if kept, with the rules they break,
+    /// keeping errors
+    pub fn check(self, rules: &[Rule]) -> Self {
+        let mut res = Self::new();
+        for item in self.items.into_iter().chain(self.totals) {
+            match item {
+                ResultItem::Data(file_info) => {
+                    for rule in rules {
+                        if let Some(actual) = rule.violated_by(&file_info) {
+                            res.add_item(ResultItem::Violation {
+                                filename: file_info.filename().to_string(),
+                                rule: rule.clone(),
+                                actual,
+                            });
+                        }
+                    }
+                }
+                item => res.add_item(item),
+            }
+        }
+        res
+    }
+
+    /// the counts of each file read, without the totals
+    pub fn file_counts(&self) -> Vec<&FileCount> {
+        self.items
+            .iter()
+            .filter_map(|item| match item {
+                ResultItem::Data(file_info) => Some(file_info),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// errors are printed to standard error, while counts are left to the caller to report
+    pub fn print_errors(&self) {
+        for item in &self.items {
+            if let ResultItem::Err { .. } = item {
+                eprintln!("{}", item);
+            }
         }
     }
+
+    pub fn violation_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches!(item, ResultItem::Violation { .. }))
+            .count()
+    }
+
+    pub fn error_count(&self) -> usize {
+        self.items
+            .iter()
+            .filter(|item| matches!(item, ResultItem::Err { .. }))
+            .count()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub enum ResultItem {
     Data(FileCount),
-    Err { filename: String, msg: String },
+    Err {
+        filename: String,
+        msg: String,
+    },
+    /// a file breaking a `--check` rule, with the value of the count the rule is about
+    Violation {
+        filename: String,
+        rule: Rule,
+        actual: usize,
+    },
 }
 impl fmt::Display for ResultItem {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
@@ -45,26 +158,70 @@ impl fmt::Display for ResultItem {
             Self::Err { filename, msg } => {
                 write!(f, "wcr: {}: {}", filename, msg)
             }
+            Self::Violation {
+                filename,
+                rule,
+                actual,
+            } => {
+                write!(f, "{}: {} failed with {}", filename, rule, actual)
+            }
         }
     }
 }
 
-#[derive(Debug, Clone, PartialEq)]
+/// where the previous read of a file stopped, so that counting can resume from there
+#[derive(Debug, Clone, Default)]
+pub(crate) struct ReadState {
+    /// number of bytes read so far
+    pub offset: u64,
+    /// whether the last line read has no trailing newline yet
+    pub ends_mid_line: bool,
+    /// the last word read, if more of it may follow
+    pub partial_word: Option<String>,
+    /// the length of the last line read, which more characters may follow
+    pub partial_line_length: usize,
+    /// the inode number of the file read, which changes when the file is replaced
+    pub inode: Option<u64>,
+    /// the modification time of the file when it was read
+    pub modified: Option<SystemTime>,
+}
+
+/// The counts computed for a single input. Counts that were not selected are `None`
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FileCount {
     filename: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
     byte_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     char_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     line_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     word_count: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_line_length: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    top: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    word_frequencies: Option<HashMap<String, usize>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    eol_report: Option<Box<EolReport>>,
+    #[serde(skip)]
+    read_state: Box<ReadState>,
 }
 impl FileCount {
-    pub fn new(filename: &str, args: &Wcr) -> Self {
+    pub(crate) fn new(filename: &str, counter: &Counter) -> Self {
         Self {
             filename: filename.to_string(),
-            byte_count: Self::value_or_default(args.show_byte_count),
-            char_count: Self::value_or_default(args.show_char_count),
-            line_count: Self::value_or_default(args.show_line_count),
-            word_count: Self::value_or_default(args.show_word_count),
+            byte_count: Self::value_or_default(counter.bytes),
+            char_count: Self::value_or_default(counter.chars),
+            line_count: Self::value_or_default(counter.lines),
+            word_count: Self::value_or_default(counter.words),
+            max_line_length: Self::value_or_default(counter.max_line_length),
+            top: counter.top,
+            word_frequencies: counter.top.map(|_| HashMap::new()),
+            eol_report: counter.eol_report.then(Box::default),
+            read_state: Box::default(),
         }
     }
     #[cfg(test)]
@@ -81,8 +238,24 @@ impl FileCount {
             char_count,
             line_count,
             word_count,
+            max_line_length: None,
+            top: None,
+            word_frequencies: None,
+            eol_report: None,
+            read_state: Box::default(),
         }
     }
+    #[cfg(test)]
+    pub fn with_top_words(mut self, top: usize, words: &[(&str, usize)]) -> Self {
+        self.top = Some(top);
+        self.word_frequencies = Some(
+            words
+                .iter()
+                .map(|(word, count)| (word.to_string(), *count))
+                .collect(),
+        );
+        self
+    }
     fn value_or_default(show_count: bool) -> Option<usize> {
         if show_count {
             Some(0)
@@ -100,36 +273,145 @@ impl FileCount {
             first_count
         }
     }
-    pub fn increment_byte_count(&mut self, count: usize) {
+    pub fn filename(&self) -> &str {
+        &self.filename
+    }
+    pub fn bytes(&self) -> Option<usize> {
+        self.byte_count
+    }
+    pub fn chars(&self) -> Option<usize> {
+        self.char_count
+    }
+    pub fn lines(&self) -> Option<usize> {
+        self.line_count
+    }
+    pub fn words(&self) -> Option<usize> {
+        self.word_count
+    }
+    pub fn max_line_length(&self) -> Option<usize> {
+        self.max_line_length
+    }
+    pub fn eol_report(&self) -> Option<&EolReport> {
+        self.eol_report.as_deref()
+    }
+    pub(crate) fn eol_report_mut(&mut self) -> Option<&mut EolReport> {
+        self.eol_report.as_deref_mut()
+    }
+    pub(crate) fn read_state(&self) -> &ReadState {
+        &self.read_state
+    }
+    pub(crate) fn set_read_state(&mut self, state: ReadState) {
+        *self.read_state = state;
+    }
+    pub(crate) fn increment_byte_count(&mut self, count: usize) {
         Self::increment_count(&mut self.byte_count, count);
     }
-    pub fn increment_char_count(&mut self, count: usize) {
+    pub(crate) fn increment_char_count(&mut self, count: usize) {
         Self::increment_count(&mut self.char_count, count);
     }
-    pub fn increment_line_count(&mut self, count: usize) {
+    pub(crate) fn increment_line_count(&mut self, count: usize) {
         Self::increment_count(&mut self.line_count, count);
     }
-    pub fn increment_word_count(&mut self, count: usize) {
+    pub(crate) fn increment_word_count(&mut self, count: usize) {
         Self::increment_count(&mut self.word_count, count);
     }
+    pub(crate) fn update_max_line_length(&mut self, line_length: usize) {
+        self.max_line_length = Some(self.max_line_length.unwrap_or(0).max(line_length));
+    }
+    pub(crate) fn increment_word_frequency(&mut self, word: &str) {
+        if let Some(frequencies) = self.word_frequencies.as_mut() {
+            *frequencies.entry(word.to_string()).or_insert(0) += 1;
+        }
+    }
+    pub(crate) fn decrement_word_frequency(&mut self, word: &str) {
+        if let Some(frequencies) = self.word_frequencies.as_mut() {
+            if let Some(count) = frequencies.get_mut(word) {
+                *count -= 1;
+                if *count == 0 {
+                    frequencies.remove(word);
+                }
+            }
+        }
+    }
+    /// the most frequent words, most frequent first and ties broken alphabetically
+    pub fn top_words(&self) -> Vec<(&str, usize)> {
+        let (Some(top), Some(frequencies)) = (self.top, &self.word_frequencies) else {
+            return vec![];
+        };
+        let mut words = frequencies
+            .iter()
+            .map(|(word, count)| (word.as_str(), *count))
+            .collect::<Vec<_>>();
+        words.sort_by(|(w1, c1), (w2, c2)| c2.cmp(c1).then_with(|| w1.cmp(w2)));
+        words.truncate(top);
+        words
+    }
+    fn merge_frequencies(
+        first: Option<HashMap<String, usize>>,
+        second: Option<HashMap<String, usize>>,
+    ) -> Option<HashMap<String, usize>> {
+        match (first, second) {
+            (Some(mut first), Some(second)) => {
+                for (word, count) in second {
+                    *first.entry(word).or_insert(0) += count;
+                }
+                Some(first)
+            }
+            (first, second) => first.or(second),
+        }
+    }
 }
-impl fmt::Display for FileCount {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+// the read state only records how far counting got, so it plays no part in comparing counts
+impl PartialEq for FileCount {
+    fn eq(&self, other: &Self) -> bool {
+        self.filename == other.filename
+            && self.byte_count == other.byte_count
+            && self.char_count == other.char_count
+            && self.line_count == other.line_count
+            && self.word_count == other.word_count
+            && self.max_line_length == other.max_line_length
+            && self.top == other.top
+            && self.word_frequencies == other.word_frequencies
+            && self.eol_report == other.eol_report
+    }
+}
+impl FileCount {
+    /// the selected counts followed by the most frequent words, without the filename
+    pub(crate) fn counts_only(&self) -> String {
+        let mut output = self.format_counts().trim_start().to_string();
+        for (word, count) in self.top_words() {
+            output.push_str(&format!("\n    {:>5} {}", count, word));
+        }
+        output
+    }
+    fn format_counts(&self) -> String {
         let mut output = String::new();
-        if self.line_count.is_some() {
-            output.push_str(&format!("{:>5}", self.line_count.unwrap()));
+        if let Some(count) = self.line_count {
+            output.push_str(&format!("{:>5}", count));
         }
-        if self.word_count.is_some() {
-            output.push_str(&format!("{:>5}", self.word_count.unwrap()));
+        if let Some(count) = self.word_count {
+            output.push_str(&format!("{:>5}", count));
         }
         // byte count and char count are mutually exclusive
-        if self.byte_count.is_some() {
-            output.push_str(&format!("{:>5}", self.byte_count.unwrap()));
+        if let Some(count) = self.byte_count {
+            output.push_str(&format!("{:>5}", count));
+        }
+        if let Some(count) = self.char_count {
+            output.push_str(&format!("{:>5}", count));
         }
-        if self.char_count.is_some() {
-            output.push_str(&format!("{:>5}", self.char_count.unwrap()));
+        if let Some(length) = self.max_line_length {
+            output.push_str(&format!("{:>5}", length));
+        }
+        output
+    }
+}
+impl fmt::Display for FileCount {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{} {}", self.format_counts(), self.filename)?;
+        for (word, count) in self.top_words() {
+            write!(f, "\n    {:>5} {}", count, word)?;
         }
-        write!(f, "{} {}", output, self.filename)
+        Ok(())
     }
 }
 impl Add for FileCount {
@@ -142,6 +424,18 @@ impl Add for FileCount {
             char_count: Self::add_counts(self.char_count, other.char_count),
             line_count: Self::add_counts(self.line_count, other.line_count),
             word_count: Self::add_counts(self.word_count, other.word_count),
+            // the longest line of all files rather than a sum, as GNU wc reports it
+            max_line_length: self.max_line_length.max(other.max_line_length),
+            top: self.top.or(other.top),
+            word_frequencies: Self::merge_frequencies(
+                self.word_frequencies,
+                other.word_frequencies,
+            ),
+            eol_report: match (self.eol_report, other.eol_report) {
+                (Some(first), Some(second)) => Some(Box::new(*first + *second)),
+                (first, second) => first.or(second),
+            },
+            read_state: Box::default(),
         }
     }
 }
@@ -3,12 +3,47 @@ use args::Wcr;
 use clap::Parser;
 
 mod args;
+mod baseline;
+mod check;
 mod counts;
+mod watch;
+
+pub use counts::{counter::Counter, result::FileCount};
 
 pub fn run() -> anyhow::Result<()> {
     let wcr = Wcr::parse().set_defaults();
+    if wcr.watch {
+        return watch::watch(&wcr);
+    }
 
     let res = counts::compute(&wcr)?;
+    if wcr.json {
+        res.print_errors();
+        println!("{}", serde_json::to_string_pretty(&res.file_counts())?);
+        return Ok(());
+    }
+    if let Some(path) = &wcr.baseline {
+        res.print_errors();
+        let deltas = baseline::compare(&res, baseline::load(path)?, &Counter::from(&wcr))?;
+        deltas.iter().for_each(|delta| println!("{}", delta));
+        return Ok(());
+    }
+    if wcr.checks.is_empty() {
+        res.print();
+        return Ok(());
+    }
+
+    let res = res.check(&wcr.checks);
     res.print();
-    Ok(())
+    match res.violation_count() {
+        0 => {}
+        1 => anyhow::bail!("1 check failed"),
+        count => anyhow::bail!("{} checks failed", count),
+    }
+    // a file that could not be read was not checked, so it cannot pass either
+    match res.error_count() {
+        0 => Ok(()),
+        1 => anyhow::bail!("1 file could not be checked"),
+        count => anyhow::bail!("{} files could not be checked", count),
+    }
 }
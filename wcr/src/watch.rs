@@ -0,0 +1,53 @@
+use crate::counts::{
+    self,
+    counter::Counter,
+    result::{FileCount, Result as CountResult, ResultItem},
+};
+use crate::Wcr;
+use std::thread;
+use std::time::Duration;
+
+/// print the counts of all files, then print them again each time one of the files changes
+pub fn watch(wcr: &Wcr) -> anyhow::Result<()> {
+    if wcr.files.iter().any(|filename| filename == "-") {
+        anyhow::bail!("standard input cannot be watched");
+    }
+    let interval = Duration::try_from_secs_f64(wcr.interval)?;
+    let counter = Counter::from(wcr);
+    let mut file_infos = wcr
+        .files
+        .iter()
+        .map(|filename| FileCount::new(filename, &counter))
+        .collect::<Vec<_>>();
+
+    let mut first_iteration = true;
+    loop {
+        let mut changed = first_iteration;
+        let mut res = CountResult::new();
+        let mut totals = FileCount::new("total", &counter);
+
+        for file_info in file_infos.iter_mut() {
+            match counts::refresh(&counter, file_info) {
+                Err(e) => res.add_item(ResultItem::Err {
+                    filename: file_info.filename().to_string(),
+                    msg: e.to_string(),
+                }),
+                Ok(refreshed) => {
+                    changed |= refreshed;
+                    res.add_item(ResultItem::Data(file_info.clone()));
+                    totals = totals + file_info.clone();
+                }
+            }
+        }
+        res.add_totals(ResultItem::Data(totals), wcr.total);
+
+        if changed {
+            if !first_iteration {
+                println!();
+            }
+            res.print();
+        }
+        first_iteration = false;
+        thread::sleep(interval);
+    }
+}
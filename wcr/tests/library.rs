@@ -0,0 +1,75 @@
+use anyhow::Result;
+use pretty_assertions::assert_eq;
+use wcr::{Counter, FileCount};
+
+const FOX_FILE: &str = "tests/inputs/fox.txt";
+
+#[test]
+fn counts_reader() -> Result<()> {
+    let counts = Counter::new()
+        .bytes(true)
+        .lines(true)
+        .words(true)
+        .count_reader("The quick brown fox\njumps over\n".as_bytes())?;
+
+    assert_eq!(counts.filename(), "-", "names reader like standard input");
+    assert_eq!(counts.bytes(), Some(31), "counts bytes");
+    assert_eq!(counts.chars(), None, "skips unselected counts");
+    assert_eq!(counts.lines(), Some(2), "counts lines");
+    assert_eq!(counts.words(), Some(6), "counts words");
+    Ok(())
+}
+
+#[test]
+fn counts_file() -> Result<()> {
+    let counts = Counter::new()
+        .chars(true)
+        .top(1)
+        .ignore_case(true)
+        .count_file(FOX_FILE)?;
+
+    assert_eq!(counts.filename(), FOX_FILE, "names counts after file");
+    assert_eq!(counts.chars(), Some(48), "counts chars");
+    assert_eq!(counts.top_words(), vec![("the", 2)], "finds top words");
+    assert!(
+        Counter::new().count_file("blargh").is_err(),
+        "fails for missing file"
+    );
+    Ok(())
+}
+
+#[test]
+fn serializes_counts() -> Result<()> {
+    let counts = Counter::new()
+        .lines(true)
+        .words(true)
+        .count_file(FOX_FILE)?;
+
+    assert_eq!(
+        serde_json::to_string(&counts)?,
+        format!(
+            r#"{{"filename":"{}","line_count":1,"word_count":9}}"#,
+            FOX_FILE
+        ),
+        "serializes selected counts"
+    );
+    Ok(())
+}
+
+#[test]
+fn deserializes_serialized_counts() -> Result<()> {
+    let counts = Counter::new()
+        .bytes(true)
+        .max_line_length(true)
+        .top(2)
+        .eol_report(true)
+        .count_file(FOX_FILE)?;
+
+    let json = serde_json::to_string(&counts)?;
+    assert_eq!(
+        serde_json::from_str::<FileCount>(&json)?,
+        counts,
+        "round trips through JSON"
+    );
+    Ok(())
+}
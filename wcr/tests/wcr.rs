@@ -3,10 +3,17 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::process::Stdio;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
 
 const EMPTY_FILE: &str = "tests/inputs/empty.txt";
 const FOX_FILE: &str = "tests/inputs/fox.txt";
 const ATLAMAL_FILE: &str = "tests/inputs/atlamal.txt";
+const EOL_FILE: &str = "tests/inputs/eol.txt";
+const BASELINE_FILE: &str = "tests/inputs/baseline.json";
 
 #[test]
 fn prints_usage() -> Result<()> {
@@ -129,3 +136,441 @@ fn assert_outputs_match(actual: &[&str], expected: &[&str], stream_name: &str) {
         );
     }
 }
+
+#[test]
+fn displays_top_words() -> Result<()> {
+    run_display_test(
+        &["--top", "2", FOX_FILE],
+        None,
+        &[
+            format!("{}{:5}{:5} {}", 1, 9, 48, FOX_FILE).as_str(),
+            format!("{:5} {}", 1, "The").as_str(),
+            format!("{:5} {}", 1, "brown").as_str(),
+        ],
+        &[""],
+    )?;
+    run_display_test(
+        &["-l", "--top", "1", "--ignore-case", FOX_FILE, FOX_FILE],
+        None,
+        &[
+            format!("{} {}", 1, FOX_FILE).as_str(),
+            format!("{:5} {}", 2, "the").as_str(),
+            format!("{} {}", 1, FOX_FILE).as_str(),
+            format!("{:5} {}", 2, "the").as_str(),
+            format!("{} total", 2).as_str(),
+            format!("{:5} {}", 4, "the").as_str(),
+        ],
+        &[""],
+    )
+}
+
+#[test]
+fn dies_when_ignoring_case_without_top() -> Result<()> {
+    Command::cargo_bin("wcr")?
+        .args(["--ignore-case", FOX_FILE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "the following required arguments were not provided",
+        ));
+    Ok(())
+}
+
+#[test]
+fn watches_files_for_changes() -> Result<()> {
+    let mut file = tempfile::NamedTempFile::new()?;
+    write!(file, "{}", fs::read_to_string(FOX_FILE)?)?;
+    let filename = file.path().to_string_lossy().to_string();
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin("wcr"))
+        .args(["--watch", "--interval", "0.05", "-lw", &filename])
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let stdout = BufReader::new(child.stdout.take().expect("stdout is piped"));
+    let (sender, lines) = mpsc::channel();
+    thread::spawn(move || {
+        stdout
+            .lines()
+            .map_while(|line| line.ok())
+            .try_for_each(|line| sender.send(line))
+    });
+    let deadline = Instant::now() + Duration::from_secs(10);
+    // the lines printed up to `last`, or all of them if the deadline passes first
+    let read_until = |last: &str| {
+        let mut read = vec![];
+        while let Some(left) = deadline.checked_duration_since(Instant::now()) {
+            match lines.recv_timeout(left) {
+                Ok(line) => {
+                    let done = line.trim() == last;
+                    read.push(line);
+                    if done {
+                        break;
+                    }
+                }
+                Err(_) => break,
+            }
+        }
+        read
+    };
+
+    let first = format!("{}{:5} {}", 1, 9, filename);
+    let mut actual_output = read_until(&first);
+    if !actual_output.is_empty() {
+        write!(file, "{}", fs::read_to_string(ATLAMAL_FILE)?)?;
+    }
+    let second = format!("{}{:5} {}", 5, 38, filename);
+    actual_output.extend(read_until(&second));
+    child.kill()?;
+    child.wait()?;
+
+    assert_outputs_match(
+        &actual_output.iter().map(String::as_str).collect::<Vec<_>>(),
+        &[first.as_str(), "", second.as_str()],
+        "stdout",
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "gzip", feature = "xz", feature = "zstd"))]
+fn counts_decompressed_contents() -> Result<()> {
+    const FOX_GZ_FILE: &str = "tests/inputs/fox.txt.gz";
+    const FOX_XZ_FILE: &str = "tests/inputs/fox.txt.xz";
+    const FOX_ZST_FILE: &str = "tests/inputs/fox.txt.zst";
+
+    for file in [FOX_GZ_FILE, FOX_XZ_FILE, FOX_ZST_FILE] {
+        run_display_test(
+            &["-z", file],
+            None,
+            &[format!("{}{:5}{:5} {}", 1, 9, 48, file).as_str()],
+            &[""],
+        )?;
+    }
+    // uncompressed inputs are counted as is
+    run_display_test(
+        &["--decompress", FOX_FILE],
+        None,
+        &[format!("{}{:5}{:5} {}", 1, 9, 48, FOX_FILE).as_str()],
+        &[""],
+    )
+}
+
+#[test]
+#[cfg(feature = "gzip")]
+fn reports_corrupt_compressed_files_and_goes_on() -> Result<()> {
+    let mut corrupt = tempfile::NamedTempFile::new()?;
+    corrupt.write_all(&fs::read("tests/inputs/fox.txt.gz")?[..20])?;
+    let corrupt_name = corrupt.path().to_string_lossy().to_string();
+
+    let output = Command::cargo_bin("wcr")?
+        .args(["-z", &corrupt_name, FOX_FILE])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_outputs_match(
+        &stdout.trim_end().split('\n').collect::<Vec<_>>(),
+        &[format!("{}{:5}{:5} {}", 1, 9, 48, FOX_FILE).as_str()],
+        "stdout",
+    );
+    assert_outputs_match(
+        &stderr.trim_end().split('\n').collect::<Vec<_>>(),
+        &[format!("wcr: {}: incomplete deflate stream", corrupt_name).as_str()],
+        "stderr",
+    );
+    Ok(())
+}
+
+#[test]
+#[cfg(all(feature = "tar", feature = "gzip"))]
+fn counts_tar_archive_members() -> Result<()> {
+    const ARCHIVE_FILE: &str = "tests/inputs/archive.tar";
+    const ARCHIVE_GZ_FILE: &str = "tests/inputs/archive.tar.gz";
+
+    run_display_test(
+        &["--tar", ARCHIVE_FILE],
+        None,
+        &[
+            format!("{}{:5}{:5} {}:fox.txt", 1, 9, 48, ARCHIVE_FILE).as_str(),
+            format!("{}{:5}{:5} {}:atlamal.txt", 4, 29, 177, ARCHIVE_FILE).as_str(),
+            format!("{}{:5}{:5} total", 5, 38, 225).as_str(),
+        ],
+        &[""],
+    )?;
+    run_display_test(
+        &["-l", "-z", "--tar", ARCHIVE_GZ_FILE, FOX_FILE],
+        None,
+        &[
+            format!("{} {}:fox.txt", 1, ARCHIVE_GZ_FILE).as_str(),
+            format!("{} {}:atlamal.txt", 4, ARCHIVE_GZ_FILE).as_str(),
+            format!("{} total", 5).as_str(),
+        ],
+        &[format!("wcr: {}: failed to read entire block", FOX_FILE).as_str()],
+    )
+}
+
+#[test]
+#[cfg(feature = "tar")]
+fn reports_unreadable_tar_members_and_goes_on() -> Result<()> {
+    let mut archive = tar::Builder::new(tempfile::NamedTempFile::new()?);
+    for (name, contents) in [
+        ("binary", &b"\xff\xfe\n"[..]),
+        ("fox.txt", &fs::read(FOX_FILE)?[..]),
+    ] {
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o644);
+        archive.append_data(&mut header, name, contents)?;
+    }
+    let archive = archive.into_inner()?;
+    let archive_name = archive.path().to_string_lossy().to_string();
+
+    let output = Command::cargo_bin("wcr")?
+        .args(["--tar", &archive_name])
+        .output()?;
+    let stdout = String::from_utf8_lossy(&output.stdout);
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert_outputs_match(
+        &stdout.trim_end().split('\n').collect::<Vec<_>>(),
+        &[format!("{}{:5}{:5} {}:fox.txt", 1, 9, 48, archive_name).as_str()],
+        "stdout",
+    );
+    assert_outputs_match(
+        &stderr.trim_end().split('\n').collect::<Vec<_>>(),
+        &[format!(
+            "wcr: {}:binary: stream did not contain valid UTF-8",
+            archive_name
+        )
+        .as_str()],
+        "stderr",
+    );
+    Ok(())
+}
+
+#[test]
+fn displays_eol_report() -> Result<()> {
+    run_display_test(
+        &["-l", "--eol-report", EOL_FILE, FOX_FILE],
+        None,
+        &[
+            format!("{} {}", 4, EOL_FILE).as_str(),
+            format!("{} {}", 1, FOX_FILE).as_str(),
+            format!("{} total", 5).as_str(),
+            "",
+            "LF   CRLF     CR  TRAIL    TAB  SPACE  MIXED  NOEOL    BOM FILE",
+            format!(
+                "{}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7} {}",
+                2, 1, 1, 1, 1, 1, 1, 1, 1, EOL_FILE
+            )
+            .as_str(),
+            format!(
+                "{}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7} {}",
+                1, 0, 0, 0, 0, 0, 0, 0, 0, FOX_FILE
+            )
+            .as_str(),
+            format!(
+                "{}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7} total",
+                3, 1, 1, 1, 1, 1, 1, 1, 1
+            )
+            .as_str(),
+        ],
+        &[""],
+    )
+}
+
+#[test]
+fn displays_eol_report_of_totals_only() -> Result<()> {
+    run_display_test(
+        &["-l", "--eol-report", "--total=only", EOL_FILE, FOX_FILE],
+        None,
+        &[
+            "5",
+            "",
+            "LF   CRLF     CR  TRAIL    TAB  SPACE  MIXED  NOEOL    BOM FILE",
+            format!(
+                "{}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7}{:>7} total",
+                3, 1, 1, 1, 1, 1, 1, 1, 1
+            )
+            .as_str(),
+        ],
+        &[""],
+    )
+}
+
+#[test]
+fn displays_max_line_length() -> Result<()> {
+    run_display_test(
+        &["-lL", ATLAMAL_FILE, FOX_FILE],
+        None,
+        &[
+            format!("{}{:5} {}", 4, 43, ATLAMAL_FILE).as_str(),
+            format!("{}{:5} {}", 1, 47, FOX_FILE).as_str(),
+            format!("{}{:5} total", 5, 47).as_str(),
+        ],
+        &[""],
+    )
+}
+
+#[test]
+fn controls_when_totals_are_displayed() -> Result<()> {
+    // files that cannot be read do not count towards showing the totals
+    run_display_test(
+        &["-l", FOX_FILE, "blargh"],
+        None,
+        &[format!("{} {}", 1, FOX_FILE).as_str()],
+        &["wcr: blargh: No such file or directory (os error 2)"],
+    )?;
+    run_display_test(
+        &["-l", "--total=always", FOX_FILE],
+        None,
+        &[format!("{} {}", 1, FOX_FILE).as_str(), "1 total"],
+        &[""],
+    )?;
+    run_display_test(
+        &["-lw", "--total=only", ATLAMAL_FILE, FOX_FILE, "blargh"],
+        None,
+        &[format!("{}{:5}", 5, 38).as_str()],
+        &["wcr: blargh: No such file or directory (os error 2)"],
+    )?;
+    run_display_test(
+        &["-l", "--total=never", ATLAMAL_FILE, FOX_FILE],
+        None,
+        &[
+            format!("{} {}", 4, ATLAMAL_FILE).as_str(),
+            format!("{} {}", 1, FOX_FILE).as_str(),
+        ],
+        &[""],
+    )
+}
+
+#[test]
+fn prints_only_the_total_counts() -> Result<()> {
+    Command::cargo_bin("wcr")?
+        .args(["-l", "--total=only", ATLAMAL_FILE, FOX_FILE])
+        .assert()
+        .success()
+        .stdout("5\n");
+    Ok(())
+}
+
+#[test]
+fn reports_check_violations() -> Result<()> {
+    Command::cargo_bin("wcr")?
+        .args([
+            "--check",
+            "lines<=1",
+            "--check",
+            "max-line-length<45",
+            ATLAMAL_FILE,
+            FOX_FILE,
+        ])
+        .assert()
+        .failure()
+        .stdout(format!(
+            "{}: lines<=1 failed with 4\n{}: max-line-length<45 failed with 47\n\
+             total: lines<=1 failed with 5\ntotal: max-line-length<45 failed with 47\n",
+            ATLAMAL_FILE, FOX_FILE
+        ))
+        .stderr(predicate::str::contains("4 checks failed"));
+
+    // the totals are checked when they are kept
+    Command::cargo_bin("wcr")?
+        .args([
+            "--check",
+            "lines<=4",
+            "--total=only",
+            ATLAMAL_FILE,
+            FOX_FILE,
+        ])
+        .assert()
+        .failure()
+        .stdout("total: lines<=4 failed with 5\n")
+        .stderr(predicate::str::contains("1 check failed"));
+
+    // files that cannot be read fail the check too
+    Command::cargo_bin("wcr")?
+        .args(["--check", "words>=9", FOX_FILE, "blargh"])
+        .assert()
+        .failure()
+        .stdout("")
+        .stderr(predicate::str::contains(
+            "wcr: blargh: No such file or directory (os error 2)",
+        ))
+        .stderr(predicate::str::contains("1 file could not be checked"));
+
+    run_display_test(
+        &["--check", "words>=9", ATLAMAL_FILE, FOX_FILE],
+        None,
+        &[""],
+        &[""],
+    )
+}
+
+#[test]
+fn dies_when_checking_watched_files() -> Result<()> {
+    Command::cargo_bin("wcr")?
+        .args(["--check", "lines<=1", "--watch", FOX_FILE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+#[test]
+fn dies_with_invalid_check() -> Result<()> {
+    Command::cargo_bin("wcr")?
+        .args(["--check", "lines<=ten", FOX_FILE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(
+            "invalid limit 'ten' in 'lines<=ten'",
+        ));
+    Ok(())
+}
+
+#[test]
+fn prints_json_snapshot() -> Result<()> {
+    let res = Command::cargo_bin("wcr")?
+        .args(["--json", "-lw", ATLAMAL_FILE, FOX_FILE])
+        .output()?;
+    assert!(res.status.success(), "command exits successfully");
+
+    let snapshot: serde_json::Value = serde_json::from_slice(&res.stdout)?;
+    assert_eq!(
+        snapshot,
+        serde_json::json!([
+            { "filename": ATLAMAL_FILE, "line_count": 4, "word_count": 29 },
+            { "filename": FOX_FILE, "line_count": 1, "word_count": 9 },
+        ]),
+        "prints counts of each file without totals"
+    );
+    Ok(())
+}
+
+#[test]
+fn displays_changes_since_baseline() -> Result<()> {
+    run_display_test(
+        &["--baseline", BASELINE_FILE, FOX_FILE, EMPTY_FILE],
+        None,
+        &[
+            format!("{:>+6}{:>+6}{:>+6} {}", -1, 4, 8, FOX_FILE).as_str(),
+            format!("{:>+6}{:>+6}{:>+6} {} (appeared)", 0, 0, 0, EMPTY_FILE).as_str(),
+            format!(
+                "{:>+6}{:>+6}{:>+6} {} (disappeared)",
+                -4, -29, -177, ATLAMAL_FILE
+            )
+            .as_str(),
+            format!("{:>+6}{:>+6}{:>+6} total", -5, -25, -169).as_str(),
+        ],
+        &[""],
+    )?;
+
+    Command::cargo_bin("wcr")?
+        .args(["-m", "--baseline", BASELINE_FILE, FOX_FILE])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains(format!(
+            "{}: the baseline has no chars count",
+            FOX_FILE
+        )));
+    Ok(())
+}